@@ -0,0 +1,96 @@
+//! This module provides an interface for interacting with Twilio accounts.
+
+use serde::Deserialize;
+
+use crate::{
+    sid::{self, Sid},
+    AuthKind,
+};
+
+/// The `Account` struct represents the metadata of a Twilio account, as
+/// returned by `Client::fetch_account`.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Account {
+    /// The date the account was created, as formatted by Twilio (RFC 2822).
+    pub date_created: String,
+    /// The human-readable name of the account.
+    pub friendly_name: String,
+    /// The SID of the account.
+    pub sid: Sid<sid::Account>,
+    /// The status of the account.
+    pub status: AccountStatus,
+    /// The type of the account.
+    #[serde(rename = "type")]
+    pub type_: AccountType,
+}
+
+/// The `Identity` struct represents who a `Client` is authenticated as, as
+/// returned by `Client::whoami`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Identity {
+    /// The SID of the account the `Client` uses.
+    pub account_sid: Sid<sid::Account>,
+    /// The human-readable name of the API key the `Client` uses, if it uses an
+    /// API key and the key is allowed to fetch its own details.
+    pub api_key_friendly_name: Option<String>,
+    /// The kind of credentials the `Client` uses.
+    pub auth_kind: AuthKind,
+    /// The human-readable name of the account the `Client` uses.
+    pub friendly_name: String,
+}
+
+/// The `AccountStatus` enum represents the status of a Twilio account.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AccountStatus {
+    /// The account is active and can be used normally.
+    Active,
+    /// The account has been suspended. Messages sent from a suspended account
+    /// will fail.
+    Suspended,
+    /// The account has been closed.
+    Closed,
+    /// The account has a status that fullsend doesn't know about yet.
+    #[serde(other)]
+    Other,
+}
+
+/// The `AccountType` enum represents the type of a Twilio account.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub enum AccountType {
+    /// The account is a trial account.
+    Trial,
+    /// The account is a full (upgraded) account.
+    Full,
+    /// The account has a type that fullsend doesn't know about yet.
+    #[serde(other)]
+    Other,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn account_deserializes() {
+        let json = r#"{
+            "date_created": "Thu, 30 Jul 2015 20:00:00 +0000",
+            "friendly_name": "fullsend",
+            "sid": "AC0123456789abcdef0123456789abcdef",
+            "status": "suspended",
+            "type": "Full"
+        }"#;
+        let account: Account = serde_json::from_str(json).unwrap();
+        assert_eq!(AccountStatus::Suspended, account.status);
+        assert_eq!(AccountType::Full, account.type_);
+        assert_eq!("fullsend", account.friendly_name);
+    }
+
+    #[test]
+    fn unknown_enum_values_deserialize_as_other() {
+        let status: AccountStatus = serde_json::from_str(r#""dormant""#).unwrap();
+        assert_eq!(AccountStatus::Other, status);
+        let type_: AccountType = serde_json::from_str(r#""Enterprise""#).unwrap();
+        assert_eq!(AccountType::Other, type_);
+    }
+}
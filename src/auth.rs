@@ -3,3 +3,22 @@ pub enum AuthMethod {
     AccountAuthToken(String),
     APIKey(String, String),
 }
+
+impl AuthMethod {
+    pub fn kind(&self) -> AuthKind {
+        match self {
+            AuthMethod::AccountAuthToken(_) => AuthKind::AuthToken,
+            AuthMethod::APIKey(_, _) => AuthKind::ApiKey,
+        }
+    }
+}
+
+/// The `AuthKind` enum represents the kind of credentials a `Client` uses to
+/// authenticate with Twilio.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AuthKind {
+    /// The `Client` uses the account's auth token.
+    AuthToken,
+    /// The `Client` uses an API key and secret.
+    ApiKey,
+}
@@ -0,0 +1,180 @@
+//! This module provides a synchronous interface for interacting with Twilio,
+//! for programs that don't use async. It's only available with the `blocking`
+//! feature.
+//!
+//! # How it works
+//!
+//! The blocking `Client` wraps an async `Client`, and runs its requests on a
+//! single-threaded tokio runtime of its own. This way, it behaves exactly like
+//! the async `Client`, with the same retries, limits, and fallback, rather
+//! than a second implementation of them that could drift apart.
+//!
+//! This doesn't cost much over `reqwest::blocking`, which also runs tokio
+//! under the hood, on a thread of its own. Like it, the blocking `Client`
+//! can't be used from within an async runtime: its functions panic if they
+//! are, as blocking there would stall the runtime's other tasks. Use the async
+//! `Client` there instead.
+
+use std::{borrow::Borrow, future::Future};
+
+use tokio::runtime::Runtime;
+
+use crate::{
+    client::{self, ClientBuilderError, SendError, SendOutcome},
+    message::MessageResponse,
+    Message,
+};
+
+/// The `Client` struct is the synchronous interface for interacting with
+/// Twilio. It has all of the settings of an async `Client`, and runs its
+/// requests on a runtime of its own, so its functions block until they're done.
+///
+/// # Creating
+///
+/// Use a `ClientBuilder`, the same as for an async `Client`:
+///
+/// ```rust
+/// # use std::env;
+/// # env::set_var("TWILIO_ACCOUNT_SID", "");
+/// # env::set_var("TWILIO_AUTH_TOKEN", "");
+/// use fullsend::blocking;
+///
+/// let client = blocking::Client::builder()
+///     .account_sid(env::var("TWILIO_ACCOUNT_SID")?)
+///     .auth_token(env::var("TWILIO_AUTH_TOKEN")?)
+///     .build_blocking();
+/// # Ok::<(), env::VarError>(())
+/// ```
+///
+/// # Panics
+///
+/// The `Client`'s functions panic if called from within an async runtime. Use
+/// the async `Client` there instead.
+#[derive(Debug)]
+pub struct Client {
+    inner: client::Client,
+    runtime: Runtime,
+}
+
+impl Client {
+    /// This function returns a `ClientBuilder` to use to create a `Client`.
+    /// Finish it with `ClientBuilder::build_blocking`.
+    pub fn builder() -> client::ClientBuilder {
+        client::ClientBuilder::default()
+    }
+
+    /// This function creates a `Client` that sends its requests with `inner`.
+    pub(crate) fn new(inner: client::Client) -> Result<Self, ClientBuilderError> {
+        let runtime = match tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+        {
+            Ok(runtime) => runtime,
+            Err(error) => return Err(ClientBuilderError::Runtime(error.to_string())),
+        };
+        Ok(Client { inner, runtime })
+    }
+
+    /// This function runs `f` with the async `Client` the blocking `Client`
+    /// wraps, blocking until the future it returns is done. This gives access
+    /// to the async `Client`'s functions that the blocking `Client` doesn't
+    /// have blocking versions of.
+    ///
+    /// ```rust,no_run
+    /// # use fullsend::blocking;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let client = blocking::Client::builder()
+    /// #     .account_sid("".into())
+    /// #     .auth_token("".into())
+    /// #     .build_blocking()?;
+    /// let message = client.block_on(|client| client.cancel_message("SM123"))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn block_on<'a, F: Future>(&'a self, f: impl FnOnce(&'a client::Client) -> F) -> F::Output {
+        self.runtime.block_on(f(&self.inner))
+    }
+
+    /// This function fetches a message sent or received by the account. See
+    /// the async `Client::fetch_message` for more.
+    pub fn fetch_message(&self, message_sid: &str) -> Result<MessageResponse, SendError> {
+        self.runtime.block_on(self.inner.fetch_message(message_sid))
+    }
+
+    /// This function sends a `Message`. See the async `Client::send_message`
+    /// for more.
+    pub fn send_message<'a>(
+        &self,
+        message: impl Borrow<Message<'a>>,
+    ) -> Result<SendOutcome, SendError> {
+        self.runtime.block_on(self.inner.send_message(message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        message::MessageStatus,
+        test_server::{Response, TestServer},
+    };
+
+    #[test]
+    fn send_message_round_trips() {
+        // the server needs a runtime of its own, as the client can't be used
+        // from within one
+        let server_runtime = tokio::runtime::Runtime::new().unwrap();
+        let server = server_runtime.block_on(TestServer::start(vec![
+            Response::new(
+                201,
+                r#"{ "sid": "SM0123456789abcdef0123456789abcdef", "status": "queued" }"#,
+            ),
+            Response::new(
+                200,
+                r#"{ "sid": "SM0123456789abcdef0123456789abcdef", "status": "delivered" }"#,
+            ),
+        ]));
+        let client = Client::builder()
+            .account_sid("AC123".into())
+            .auth_token("token".into())
+            .base_url(server.url())
+            .build_blocking()
+            .unwrap();
+        let message = Message::builder()
+            .to("+12025550189")
+            .from("+12025550123")
+            .body("hi")
+            .build()
+            .unwrap();
+        let outcome = client.send_message(&message).unwrap();
+        let sid = outcome.into_twilio_response().unwrap().sid;
+        let message_response = client.fetch_message(sid.as_str()).unwrap();
+        assert_eq!(MessageStatus::Delivered, message_response.status);
+        let requests = server.requests();
+        assert_eq!(2, requests.len());
+        assert_eq!("hi", requests[0].form()["Body"]);
+        assert_eq!(
+            "/2010-04-01/Accounts/AC123/Messages/SM0123456789abcdef0123456789abcdef.json",
+            requests[1].path
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "from within a runtime")]
+    fn panics_within_runtime() {
+        let client = Client::builder()
+            .account_sid("".into())
+            .auth_token("".into())
+            .build_blocking()
+            .unwrap();
+        let message = Message::builder()
+            .to("")
+            .from("")
+            .content_variables([("name", "Bob")].into())
+            .build()
+            .unwrap();
+        tokio_test::block_on(async {
+            let _ = client.send_message(&message);
+        });
+    }
+}
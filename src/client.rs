@@ -1,6 +1,40 @@
 //! This module provides an interface for interacting with Twilio.
 
-use crate::{auth::AuthMethod, Message};
+use std::{
+    borrow::{Borrow, Cow},
+    collections::{hash_map::RandomState, HashMap},
+    env, fmt,
+    hash::{BuildHasher, Hasher},
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::SecondsFormat;
+use futures::{stream, Stream, StreamExt};
+use reqwest::{
+    header::{HeaderMap, RETRY_AFTER},
+    redirect::Policy,
+    RequestBuilder,
+};
+use serde::{
+    de::{self, DeserializeOwned},
+    ser, Deserialize,
+};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::{
+    account::{Account, Identity},
+    auth::{AuthKind, AuthMethod},
+    content::ContentTemplate,
+    fallback::FallbackSender,
+    lookup::{LookupField, LookupResult},
+    message::{
+        self, Content, MessageListFilter, MessagePage, MessageResponse, MessageStatus,
+        ScheduledMessage,
+    },
+    sid::{self, Sid},
+    Message,
+};
 
 /// The `Client` struct is the interface for interacting with Twilio.
 ///
@@ -37,28 +71,292 @@ use crate::{auth::AuthMethod, Message};
 ///     .build();
 /// # Ok::<(), env::VarError>(())
 /// ```
-#[derive(Debug, PartialEq)]
+#[derive(Debug)]
 pub struct Client {
     account_sid: String,
     auth: AuthMethod,
+    base_url: String,
+    default_content_sid: Option<String>,
+    default_country_code: Option<String>,
+    edge: Option<String>,
+    fallback: Option<Arc<dyn FallbackSender>>,
+    fetch_timeout: Option<Duration>,
+    from_pool: Vec<String>,
+    #[cfg(feature = "gzip")]
+    gzip_request: bool,
+    http: reqwest::Client,
+    list_timeout: Option<Duration>,
+    #[cfg(feature = "tracing")]
+    log_requests: bool,
+    max_concurrent_sends: Option<usize>,
+    max_retries: u32,
+    max_segments_per_message: Option<u32>,
+    on_retry: Option<OnRetry>,
+    redact_audit_records: bool,
+    redirect_policy: Option<Arc<Policy>>,
+    region: Option<String>,
+    retry_base_delay: Duration,
+    retry_rng: RetryRng,
+    send_permits: Option<Arc<Semaphore>>,
+    send_timeout: Option<Duration>,
+    tcp_nodelay: bool,
+    timeout: Option<Duration>,
+}
+
+/// The `AuditRecord` struct records a message sent with
+/// `Client::send_message_audited` and Twilio's response to it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuditRecord {
+    /// The form params that were sent, redacted if the `Client` is set to
+    /// redact audit records.
+    pub request_params: Vec<(String, String)>,
+    /// The URL the request was sent to.
+    pub request_url: String,
+    /// The body of Twilio's response, redacted if the `Client` is set to
+    /// redact audit records.
+    pub response_body: String,
+    /// The HTTP status code of Twilio's response.
+    pub response_status: u16,
+    /// The SID of the message, if Twilio created it.
+    pub sid: Option<Sid<sid::Message>>,
+}
+
+/// The `PreparedRequest` struct describes the request `Client::send_message`
+/// would send for a message, as returned by `Client::preview_message`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PreparedRequest {
+    /// The username the request authenticates with. The password is left out.
+    pub auth_username: String,
+    /// The extra headers set on the message.
+    pub headers: Vec<(String, String)>,
+    /// The form params of the request.
+    pub params: Vec<(String, String)>,
+    /// The URL the request is sent to.
+    pub url: String,
+}
+
+impl PreparedRequest {
+    /// This function returns a `curl` command that sends the request, for
+    /// reproducing it or sharing it with Twilio support. The password is
+    /// masked as `***`, so it has to be filled in before the command is run.
+    pub fn to_curl(&self) -> String {
+        self.curl("***")
+    }
+
+    /// This function returns a `curl` command that sends the request, like
+    /// `to_curl`, but with `password` filled in so that the command can be run
+    /// as is. Be careful where you share it.
+    pub fn to_curl_with_password(&self, password: &str) -> String {
+        self.curl(password)
+    }
+
+    /// This function returns a `curl` command that sends the request,
+    /// authenticating with `password`.
+    fn curl(&self, password: &str) -> String {
+        let mut command = format!("curl -X POST {}", shell_quote(&self.url));
+        command.push_str(" \\\n  -u ");
+        command.push_str(&shell_quote(&format!("{}:{password}", self.auth_username)));
+        for (name, value) in &self.headers {
+            command.push_str(" \\\n  -H ");
+            command.push_str(&shell_quote(&format!("{name}: {value}")));
+        }
+        // curl encodes the value, but not the name, which is fine for ours
+        for (name, value) in &self.params {
+            command.push_str(" \\\n  --data-urlencode ");
+            command.push_str(&shell_quote(&format!("{name}={value}")));
+        }
+        command
+    }
+}
+
+/// The `SendOutcome` enum represents how a message sent with
+/// `Client::send_message` went out.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SendOutcome {
+    /// Twilio couldn't be reached, so the message was sent by the `Client`'s
+    /// fallback instead. There's no response from Twilio to follow up on.
+    Fallback,
+    /// Twilio accepted the message. Its response is contained in this
+    /// outcome.
+    Twilio(Box<MessageResponse>),
+}
+
+impl SendOutcome {
+    /// This function returns Twilio's response to the message, or `None` if
+    /// the message was sent by the fallback.
+    pub fn twilio_response(&self) -> Option<&MessageResponse> {
+        match self {
+            SendOutcome::Fallback => None,
+            SendOutcome::Twilio(message_response) => Some(message_response),
+        }
+    }
+
+    /// This function returns Twilio's response to the message, or `None` if
+    /// the message was sent by the fallback, consuming the outcome.
+    pub fn into_twilio_response(self) -> Option<MessageResponse> {
+        match self {
+            SendOutcome::Fallback => None,
+            SendOutcome::Twilio(message_response) => Some(*message_response),
+        }
+    }
+}
+
+/// The body of Twilio's response to an API key being fetched, as far as we
+/// need it.
+#[derive(Deserialize)]
+struct ApiKey {
+    friendly_name: String,
+}
+
+/// The `Hook` struct holds a function set on a `ClientBuilder`, like with
+/// `ClientBuilder::on_retry`, so that the `Client` can still be debugged.
+struct Hook<F: ?Sized>(Arc<F>);
+
+impl<F: ?Sized> Clone for Hook<F> {
+    fn clone(&self) -> Self {
+        Hook(Arc::clone(&self.0))
+    }
+}
+
+impl<F: ?Sized> fmt::Debug for Hook<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Hook")
+    }
 }
 
+/// The function called before each retry, set with `ClientBuilder::on_retry`.
+type OnRetry = Hook<dyn Fn(u32, &SendError) + Send + Sync>;
+
+/// The random number generator used to pick how long to wait before a retry,
+/// set with `ClientBuilder::retry_rng`.
+type RetryRng = Hook<dyn Fn() -> f64 + Send + Sync>;
+
 /// The `SendError` enum represents the various types of errors that can arise
 /// when attempting to send a `Message`.
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum SendError {
+    /// This error occurs when the request body couldn't be compressed. The
+    /// `std::io` error is contained in this error.
+    #[cfg(feature = "gzip")]
+    #[error("couldn't compress request body")]
+    Compression(#[from] std::io::Error),
+    /// This error occurs when a message didn't get a final status in time
+    /// while waiting for it with `Client::wait_for_delivery`. The last status
+    /// the message had is contained in this error.
+    #[error("message still {status:?} when time ran out")]
+    DeliveryTimeout {
+        /// The last status the message had.
+        status: MessageStatus,
+    },
     /// This error occurs when there was an error serializing the content
-    /// variables. The `serde_json` error is contained in this error.
-    #[error("couldn't serialize content variables")]
+    /// variables or parsing a response from Twilio. The `serde_json` error is
+    /// contained in this error.
+    #[error("couldn't serialize or deserialize JSON")]
     Json(#[from] serde_json::Error),
     /// This error occurs when there was an error communicating with Twilio.
     /// The `reqwest` error is contained in this error.
     #[error("couldn't communicate with twilio")]
     Network(#[from] reqwest::Error),
+    /// This error occurs when a message only has Content Variables, but the
+    /// `Client` has no default Content SID to use with them.
+    #[error("no content SID set in message or client")]
+    NoContentSid,
+    /// This error occurs when a message sent with `Client::send_scheduled`
+    /// isn't scheduled with `MessageBuilder::scheduled_at`.
+    #[error("message isn't scheduled")]
+    NotScheduled,
+    /// This error occurs when a message's body would be sent as more segments
+    /// than the `Client` allows per message. The number of segments and the
+    /// maximum are contained in this error.
+    #[error("body would be sent as {segments} segments, more than the maximum of {max}")]
+    TooManySegments {
+        /// The maximum number of segments the `Client` allows per message.
+        max: u32,
+        /// The number of segments the body would be sent as.
+        segments: u32,
+    },
     /// This error occurs when Twilio was able to be contacted, but the request
-    /// was unsuccessful. The HTTP response code is contained in this error.
-    #[error("Twilio returned reponse code {0}")]
-    Twilio(u16),
+    /// was unsuccessful. The HTTP response code is contained in this error,
+    /// along with the details of the error from Twilio's response, if it had
+    /// them.
+    #[error("Twilio returned reponse code {status}")]
+    Twilio {
+        /// Twilio's code for the error, like `21211` for an invalid `To`
+        /// number.
+        code: Option<u32>,
+        /// Twilio's description of the error.
+        message: Option<String>,
+        /// The URL of Twilio's documentation for the error.
+        more_info: Option<String>,
+        /// The HTTP response code.
+        status: u16,
+    },
+}
+
+impl SendError {
+    /// This function returns a short label for the kind of error this is,
+    /// suitable for bucketing errors in metrics. The labels are:
+    ///
+    /// - `"network"`: Twilio couldn't be reached
+    /// - `"timeout"`: Twilio didn't respond in time, or a message wasn't
+    ///   delivered in time
+    /// - `"rate_limited"`: Twilio responded with `429 Too Many Requests`
+    /// - `"client_error"`: the request was rejected by Twilio with any other
+    ///   `4xx` response, or the message was refused before being sent
+    /// - `"server_error"`: Twilio responded with a `5xx` response
+    /// - `"twilio_api"`: Twilio responded with any other unsuccessful response
+    /// - `"internal"`: the request body couldn't be built, or the response
+    ///   couldn't be read
+    ///
+    /// These labels won't change between versions, although new ones may be
+    /// added along with new errors.
+    pub fn category(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "gzip")]
+            SendError::Compression(_) => "internal",
+            SendError::DeliveryTimeout { .. } => "timeout",
+            SendError::Json(_) => "internal",
+            SendError::Network(error) if error.is_timeout() => "timeout",
+            SendError::Network(_) => "network",
+            SendError::NoContentSid => "client_error",
+            SendError::NotScheduled => "client_error",
+            SendError::TooManySegments { .. } => "client_error",
+            SendError::Twilio { status: 429, .. } => "rate_limited",
+            SendError::Twilio {
+                status: 400..=499, ..
+            } => "client_error",
+            SendError::Twilio {
+                status: 500..=599, ..
+            } => "server_error",
+            SendError::Twilio { .. } => "twilio_api",
+        }
+    }
+
+    /// This function returns whether Twilio couldn't be reached, including
+    /// when it didn't respond in time.
+    pub fn is_network(&self) -> bool {
+        matches!(self, SendError::Network(_))
+    }
+
+    /// This function returns the HTTP status code of Twilio's response, if
+    /// Twilio responded with an error.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            SendError::Twilio { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// This function returns Twilio's error code, like `21211` for an invalid
+    /// `To` number, if Twilio responded with one. See
+    /// <https://www.twilio.com/docs/api/errors> for what the codes mean.
+    pub fn twilio_code(&self) -> Option<u32> {
+        match self {
+            SendError::Twilio { code, .. } => *code,
+            _ => None,
+        }
+    }
 }
 
 impl Client {
@@ -67,186 +365,3088 @@ impl Client {
         ClientBuilder::default()
     }
 
-    /// This function sends a `Message`.
+    /// This function creates a `Client` from the standard Twilio environment
+    /// variables. The account SID is read from `TWILIO_ACCOUNT_SID`. If
+    /// `TWILIO_API_KEY` is set, the `Client` authenticates with it and
+    /// `TWILIO_API_SECRET`; otherwise, it uses `TWILIO_AUTH_TOKEN`.
     ///
-    /// # Return value
+    /// Use `ClientBuilder` instead if you need to change any other settings.
+    pub fn from_env() -> Result<Client, ClientBuilderError> {
+        let mut builder = ClientBuilder::new();
+        builder.account_sid(env_var("TWILIO_ACCOUNT_SID")?);
+        // api keys are preferred, since they can be revoked without changing
+        // the auth token
+        match env::var("TWILIO_API_KEY") {
+            Ok(key) => builder.api_key(key, env_var("TWILIO_API_SECRET")?),
+            Err(_) => builder.auth_token(env_var("TWILIO_AUTH_TOKEN")?),
+        };
+        builder.build()
+    }
+
+    /// This function creates a `Client` that uses your account's test
+    /// credentials, found in the Twilio console. It's the same as building a
+    /// `Client` with `account_sid` and `auth_token`, but makes it clear that
+    /// the `Client` is only for testing.
     ///
-    /// If the return is `Ok`, this does not necessarily mean that the message
-    /// was delivered to the destination. This only means that the communication
-    /// with Twilio was successful, and the request was valid.
-    pub async fn send_message(&self, message: &Message<'_>) -> Result<(), SendError> {
-        // in order to avoid having our params map reallocate every time we push
-        // one, we're going to count the number we need, then allocate once.
-        // we know for sure we have one: the message destination, so we'll start
-        // with one.
-        let mut num_params = 1;
-        // now let's sort out the sender items
-        if message.from.is_some() {
-            num_params += 1;
-        }
-        if message.messaging_service_sid.is_some() {
-            num_params += 1;
-        }
-        // now, the content items. i'll save the media URL(s) for last because
-        // they're a bit more tricky
-        if message.body.is_some() {
-            num_params += 1;
-        }
-        if message.content_sid.is_some() {
-            num_params += 1;
-        }
-        if message.content_variables.is_some() {
-            num_params += 1;
-        }
-        if message.media_urls.is_some() {
-            // like i said
-            num_params += message.media_urls.as_ref().unwrap().len();
-        }
-        // now that we know how many params we need, let's create our params
-        let mut params = Vec::<(&str, &str)>::with_capacity(num_params);
-        params.push(("To", message.to));
-        if let Some(from) = message.from {
-            params.push(("From", from));
-        }
-        if let Some(messaging_service_sid) = message.messaging_service_sid {
-            params.push(("MessagingServiceSid", messaging_service_sid));
-        }
-        if let Some(body) = message.body {
-            params.push(("Body", body));
-        }
-        if let Some(content_sid) = message.content_sid {
-            params.push(("ContentSid", content_sid));
-        }
-        let content_variables_json: String;
-        if let Some(content_variables) = &message.content_variables {
-            content_variables_json = match serde_json::to_string(&content_variables) {
-                Ok(content_variables_json) => content_variables_json,
-                Err(error) => return Err(SendError::Json(error)),
-            };
-            params.push(("ContentVariables", &content_variables_json));
+    /// Messages sent with test credentials aren't delivered, and only succeed
+    /// or fail depending on the numbers they use. See the `test_numbers`
+    /// module for the numbers Twilio accepts. Twilio's test account SIDs look
+    /// just like live ones, so make sure not to pass your live credentials.
+    pub fn test_credentials(
+        account_sid: String,
+        auth_token: String,
+    ) -> Result<Client, ClientBuilderError> {
+        ClientBuilder::new()
+            .account_sid(account_sid)
+            .auth_token(auth_token)
+            .build()
+    }
+
+    /// This function returns a `ClientBuilder` with all of this `Client`'s
+    /// settings, including its credentials. This is useful for creating a
+    /// `Client` that only differs from this one in a few settings.
+    pub fn to_builder(&self) -> ClientBuilder {
+        ClientBuilder {
+            account_sid: Some(self.account_sid.clone()),
+            auth: Some(self.auth.clone()),
+            // only keep the url if it was set, so that changing the region
+            // or edge still changes it
+            base_url: if self.base_url == regional_url("api", &self.edge, &self.region) {
+                None
+            } else {
+                Some(self.base_url.clone())
+            },
+            default_content_sid: self.default_content_sid.clone(),
+            default_country_code: self.default_country_code.clone(),
+            edge: self.edge.clone(),
+            fallback: self.fallback.clone(),
+            fetch_timeout: self.fetch_timeout,
+            from_pool: self.from_pool.clone(),
+            #[cfg(feature = "gzip")]
+            gzip_request: self.gzip_request,
+            list_timeout: self.list_timeout,
+            #[cfg(feature = "tracing")]
+            log_requests: self.log_requests,
+            max_concurrent_sends: self.max_concurrent_sends,
+            max_retries: self.max_retries,
+            max_segments_per_message: self.max_segments_per_message,
+            on_retry: self.on_retry.clone(),
+            redact_audit_records: self.redact_audit_records,
+            redirect_policy: self.redirect_policy.clone(),
+            region: self.region.clone(),
+            retry_base_delay: self.retry_base_delay,
+            retry_rng: self.retry_rng.clone(),
+            send_timeout: self.send_timeout,
+            tcp_nodelay: self.tcp_nodelay,
+            timeout: self.timeout,
         }
-        if let Some(media_urls) = &message.media_urls {
-            for media_url in media_urls {
-                params.push(("MediaUrl", media_url));
-            }
+    }
+
+    /// This function returns the kind of credentials the `Client` uses to
+    /// authenticate with Twilio. Some features, such as validating webhook
+    /// signatures, need the account's auth token rather than an API key.
+    pub fn auth_kind(&self) -> AuthKind {
+        self.auth.kind()
+    }
+
+    /// This function returns the username the `Client` authenticates with:
+    /// the account SID when using the account's auth token, or the key SID
+    /// when using an API key. It never reveals the secret.
+    pub fn auth_username(&self) -> &str {
+        self.basic_auth().0
+    }
+
+    /// This function cancels a message scheduled with
+    /// `MessageBuilder::scheduled_at`, so that it isn't sent. Messages that
+    /// have already been sent can't be canceled.
+    pub async fn cancel_message(&self, message_sid: &str) -> Result<MessageResponse, SendError> {
+        let _permit = self.send_permit().await;
+        let twilio_result = self
+            .send_request(self.http.post(self.message_url(message_sid)))
+            .form(&[("Status", "canceled")])
+            .send()
+            .await;
+        match twilio_result {
+            Ok(response) => MessageResponse::from_response(response).await,
+            Err(error) => Err(SendError::Network(error)),
         }
-        // let's get our auth situation sorted
-        let auth_user: &str;
-        let auth_pass: &str;
-        match &self.auth {
-            AuthMethod::AccountAuthToken(token) => {
-                auth_user = &self.account_sid;
-                auth_pass = token;
-            }
-            AuthMethod::APIKey(key, secret) => {
-                auth_user = key;
-                auth_pass = secret;
-            }
-        };
-        // now that we have our params and auth sorted, we can send the request
-        let client = reqwest::Client::new();
-        let twilio_result = client
-            .post(format!(
-                "https://api.twilio.com/2010-04-01/Accounts/{}/Messages.json",
-                self.account_sid
-            ))
-            .form(&params)
-            .basic_auth(auth_user, Some(auth_pass))
+    }
+
+    /// This function deletes a message from the account, removing its body and
+    /// other details from Twilio. This is useful for privacy compliance once a
+    /// message has been delivered.
+    pub async fn delete_message(&self, message_sid: &str) -> Result<(), SendError> {
+        let _permit = self.send_permit().await;
+        let twilio_result = self
+            .send_request(self.http.delete(self.message_url(message_sid)))
             .send()
             .await;
         let twilio_response = match twilio_result {
             Ok(response) => response,
             Err(error) => return Err(SendError::Network(error)),
         };
+        // twilio responds with 204 No Content when it's done
         if twilio_response.status().is_success() {
             Ok(())
         } else {
-            Err(SendError::Twilio(twilio_response.status().as_u16()))
+            Err(twilio_error(twilio_response).await)
         }
     }
-}
 
-/// The `ClientBuilderError` enum represents the various types of errors that
-/// can arise when attempting to build a `ClientBuilder`.
-#[derive(Debug, thiserror::Error, PartialEq)]
-pub enum ClientBuilderError {
-    /// This error occurs when you attempt to build a `ClientBuilder` without
-    /// setting an account SID.
-    #[error("no account SID set in builder")]
-    NoAccountSidSet,
-    /// This error occurs when you attempt to build a `ClientBuilder` without
-    /// setting an authentication method, either the account's auth token or an
-    /// API key and secret.
-    #[error("no auth method set in builder")]
-    NoAuthMethodSet,
-}
+    /// This function fetches the metadata of the account the `Client` is
+    /// configured to use.
+    ///
+    /// This is useful for checking the account's status: messages sent from a
+    /// suspended account will fail.
+    pub async fn fetch_account(&self) -> Result<Account, SendError> {
+        self.fetch(format!("{}.json", self.account_url()), self.fetch_timeout)
+            .await
+    }
 
-/// The `ClientBuilder` struct is used to create a `Client`.
-#[derive(Default)]
-pub struct ClientBuilder {
-    account_sid: Option<String>,
-    auth: Option<AuthMethod>,
-}
+    /// This function fetches a Twilio Content template, so that you can see
+    /// what it contains or preview it with `ContentTemplate::render` before
+    /// sending messages with it.
+    pub async fn fetch_content(&self, content_sid: &str) -> Result<ContentTemplate, SendError> {
+        self.fetch(
+            format!(
+                "{}/Content/{content_sid}",
+                regional_url("content", &self.edge, &self.region)
+            ),
+            self.fetch_timeout,
+        )
+        .await
+    }
 
-impl ClientBuilder {
-    /// This function creates a `ClientBuilder`.
-    pub fn new() -> Self {
-        ClientBuilder {
-            account_sid: None,
-            auth: None,
+    /// This function fetches a message every `poll_interval` until Twilio has
+    /// billed it, and returns its price. Twilio doesn't know the price when the
+    /// message is created, so this is useful for reconciling costs. The price
+    /// is in the currency of the message's `MessageResponse::price_unit`, and
+    /// is negative, as it's a charge to the account.
+    ///
+    /// If the message hasn't been billed within `timeout`, this returns
+    /// `Ok(None)`. Errors fetching the message are returned as soon as they
+    /// happen, as is a `SendError::Json` error if Twilio's price isn't a
+    /// number.
+    pub async fn fetch_price(
+        &self,
+        message_sid: &str,
+        poll_interval: Duration,
+        timeout: Duration,
+    ) -> Result<Option<f64>, SendError> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let message = self.fetch_message(message_sid).await?;
+            if let Some(price) = message.price {
+                return match price.parse() {
+                    Ok(price) => Ok(Some(price)),
+                    Err(_) => Err(SendError::Json(de::Error::custom(format!(
+                        "invalid price {price:?}"
+                    )))),
+                };
+            }
+            // there's no point waiting if we won't get to check again
+            if tokio::time::Instant::now() + poll_interval > deadline {
+                return Ok(None);
+            }
+            tokio::time::sleep(poll_interval).await;
         }
     }
 
-    /// This function validates the builder chain and returns a `Client` that
-    /// you can then use to interact with Twilio.
-    pub fn build(&self) -> Result<Client, ClientBuilderError> {
-        if self.account_sid.is_none() {
-            return Err(ClientBuilderError::NoAccountSidSet);
+    /// This function fetches a message sent or received by the account, which
+    /// is useful for checking whether a message you sent has been delivered.
+    pub async fn fetch_message(&self, message_sid: &str) -> Result<MessageResponse, SendError> {
+        self.fetch(self.message_url(message_sid), self.fetch_timeout)
+            .await
+    }
+
+    /// This function fetches many messages, with at most `concurrency` requests
+    /// in flight at once. Each message is fetched the same way as with
+    /// `fetch_message`, which is useful for checking on a batch of messages
+    /// sent with `send_many`.
+    ///
+    /// # Return value
+    ///
+    /// The results are returned in the same order as `message_sids`, so that
+    /// failures can be matched up with the messages that failed.
+    pub async fn fetch_messages(
+        &self,
+        message_sids: &[&str],
+        concurrency: usize,
+    ) -> Vec<Result<MessageResponse, SendError>> {
+        stream::iter(message_sids)
+            .map(|message_sid| self.fetch_message(message_sid))
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    /// This function fetches who the `Client` is authenticated as: the account
+    /// it uses, and, for API keys, the name of the key. This is useful for
+    /// making sure you're pointed at the right account, like production rather
+    /// than staging.
+    ///
+    /// Only some API keys are allowed to fetch their own details, so
+    /// `Identity::api_key_friendly_name` is `None` if Twilio refuses with
+    /// `401 Unauthorized`, `403 Forbidden`, or `404 Not Found`. Any other
+    /// error is returned.
+    pub async fn whoami(&self) -> Result<Identity, SendError> {
+        let account = self.fetch_account().await?;
+        let api_key_friendly_name = match &self.auth {
+            AuthMethod::APIKey(key, _) => {
+                let api_key_result = self
+                    .fetch::<ApiKey>(
+                        format!("{}/Keys/{key}.json", self.account_url()),
+                        self.fetch_timeout,
+                    )
+                    .await;
+                match api_key_result {
+                    Ok(api_key) => Some(api_key.friendly_name),
+                    // the key isn't allowed to see itself, which is fine
+                    Err(SendError::Twilio {
+                        status: 401 | 403 | 404,
+                        ..
+                    }) => None,
+                    Err(error) => return Err(error),
+                }
+            }
+            AuthMethod::AccountAuthToken(_) => None,
+        };
+        Ok(Identity {
+            account_sid: account.sid,
+            api_key_friendly_name,
+            auth_kind: self.auth_kind(),
+            friendly_name: account.friendly_name,
+        })
+    }
+
+    /// This function lists the messages sent and received by the account that
+    /// match `filter`, newest first. Only the first page of messages is
+    /// returned; pass it to `next_messages_page` to get the rest.
+    pub async fn list_messages(
+        &self,
+        filter: &MessageListFilter<'_>,
+    ) -> Result<MessagePage, SendError> {
+        let query = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(filter.query_params())
+            .finish();
+        let mut url = self.messages_url();
+        if !query.is_empty() {
+            url.push('?');
+            url.push_str(&query);
         }
-        let account_sid = self.account_sid.clone().unwrap();
-        if self.auth.is_none() {
-            return Err(ClientBuilderError::NoAuthMethodSet);
+        self.fetch(url, self.list_timeout).await
+    }
+
+    /// This function looks up `phone_number` with Twilio Lookup, which is
+    /// useful for checking that a number is valid before sending to it.
+    /// National numbers are assumed to be from the US; use E.164 format for
+    /// others.
+    ///
+    /// Validation is free, but each of the extra `fields` requested is billed
+    /// per lookup. Request `LookupField::LineTypeIntelligence` to check
+    /// whether the number can receive SMS.
+    pub async fn lookup(
+        &self,
+        phone_number: &str,
+        fields: &[LookupField],
+    ) -> Result<LookupResult, SendError> {
+        self.fetch(self.lookup_url(phone_number, fields), self.fetch_timeout)
+            .await
+    }
+
+    /// This function fetches the page of messages after `page`, or returns
+    /// `None` if `page` is the last one.
+    pub async fn next_messages_page(
+        &self,
+        page: &MessagePage,
+    ) -> Result<Option<MessagePage>, SendError> {
+        let next_page_uri = match &page.next_page_uri {
+            Some(next_page_uri) => next_page_uri,
+            None => return Ok(None),
+        };
+        let url = self.resolve_uri(next_page_uri);
+        match self.fetch(url, self.list_timeout).await {
+            Ok(next_page) => Ok(Some(next_page)),
+            Err(error) => Err(error),
         }
-        let auth = self.auth.clone().unwrap();
-        Ok(Client { account_sid, auth })
     }
 
-    /// This function sets the account SID to be used by the `Client` when
-    /// interacting with Twilio.
-    pub fn account_sid(&mut self, account_sid: String) -> &mut Self {
-        self.account_sid = Some(account_sid);
-        self
+    /// This function returns the request that `send_message` would send for
+    /// `message`, without sending it. This is useful for dry runs and tests.
+    /// The request is checked the same way as by `send_message`, so an error
+    /// means the message wouldn't be sent either.
+    ///
+    /// The params aren't redacted, but the `Client`'s credentials are never
+    /// included.
+    pub fn preview_message(&self, message: &Message<'_>) -> Result<PreparedRequest, SendError> {
+        self.check_segments(message.body.as_deref())?;
+        let mut params = message_params(
+            message,
+            self.default_content_sid.as_deref(),
+            self.default_country_code.as_deref(),
+        )?;
+        if let Some(from) = self.pool_sender(&params, 0) {
+            params.push(("From", Cow::Borrowed(from)));
+        }
+        Ok(PreparedRequest {
+            auth_username: self.auth_username().into(),
+            headers: message
+                .headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            params: params
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into_owned()))
+                .collect(),
+            url: self.messages_url(),
+        })
     }
 
-    /// This function sets the API key to be used to authenticate the `Client`
-    /// with Twilio.
-    pub fn api_key(&mut self, key: String, secret: String) -> &mut Self {
-        self.auth = Some(AuthMethod::APIKey(key, secret));
-        self
+    /// This function sends a message using a Twilio Content template through a
+    /// Messaging Service, the shape Twilio recommends for template-based
+    /// messages. `content_variables` are serialized to JSON for you, and are
+    /// omitted if empty.
+    ///
+    /// # Return value
+    ///
+    /// As with `send_message`, an `Ok` only means that Twilio accepted the
+    /// message.
+    pub async fn send_content_message(
+        &self,
+        to: &str,
+        messaging_service_sid: &str,
+        content_sid: &str,
+        content_variables: &HashMap<&str, &str>,
+    ) -> Result<MessageResponse, SendError> {
+        let mut params = Vec::<(&str, Cow<str>)>::with_capacity(4);
+        params.push((
+            "To",
+            with_country_code(to, self.default_country_code.as_deref()),
+        ));
+        params.push(("MessagingServiceSid", Cow::Borrowed(messaging_service_sid)));
+        params.push(("ContentSid", Cow::Borrowed(content_sid)));
+        if !content_variables.is_empty() {
+            let content_variables_json = match serde_json::to_string(content_variables) {
+                Ok(content_variables_json) => content_variables_json,
+                Err(error) => return Err(SendError::Json(error)),
+            };
+            params.push(("ContentVariables", Cow::Owned(content_variables_json)));
+        }
+        self.post_message(&params, &[]).await
     }
 
-    /// This function sets the account auth token to be used to authenticate the
-    /// `Client` with Twilio.
-    pub fn auth_token(&mut self, token: String) -> &mut Self {
-        self.auth = Some(AuthMethod::AccountAuthToken(token));
-        self
+    /// This function sends many messages, with at most `concurrency` requests
+    /// in flight at once. Each message is sent the same way as with
+    /// `send_message`.
+    ///
+    /// # Return value
+    ///
+    /// The results are returned in the same order as `messages`, so that
+    /// failures can be matched up with the messages that failed.
+    pub async fn send_many(
+        &self,
+        messages: &[Message<'_>],
+        concurrency: usize,
+    ) -> Vec<Result<SendOutcome, SendError>> {
+        stream::iter(messages)
+            .map(|message| self.send_message(message))
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// This function sends a `Message`.
+    ///
+    /// # Return value
+    ///
+    /// If the return is `Ok`, this does not necessarily mean that the message
+    /// was delivered to the destination. This only means that the communication
+    /// with Twilio was successful, and the request was valid. The returned
+    /// `SendOutcome::Twilio` contains Twilio's `MessageResponse`, with the
+    /// message's SID and initial status, which can be used to follow up on it.
+    ///
+    /// If Twilio can't be reached and the `Client` has a fallback set with
+    /// `ClientBuilder::fallback`, the message is passed to the fallback. If the
+    /// fallback sends it, `SendOutcome::Fallback` is returned, as there's no
+    /// response from Twilio. If it doesn't, the fallback's error is returned.
+    ///
+    /// # Passing the message
+    ///
+    /// The message can be passed either by reference or by value, or in
+    /// anything else that can be borrowed as a `Message`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(auth_kind = ?self.auth_kind(), to = tracing::field::Empty),
+        )
+    )]
+    pub async fn send_message<'a>(
+        &self,
+        message: impl Borrow<Message<'a>>,
+    ) -> Result<SendOutcome, SendError> {
+        let message = message.borrow();
+        // the destination is masked, like it is in logged params
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("to", message::mask_number(&message.to).as_str());
+        self.check_segments(message.body.as_deref())?;
+        let params = message_params(
+            message,
+            self.default_content_sid.as_deref(),
+            self.default_country_code.as_deref(),
+        )?;
+        match self.post_message(&params, &message.headers).await {
+            Ok(message_response) => Ok(SendOutcome::Twilio(Box::new(message_response))),
+            // twilio couldn't be reached, so let's try the fallback if there
+            // is one
+            Err(SendError::Network(error)) => match &self.fallback {
+                Some(fallback) => match fallback.send(message, SendError::Network(error)).await {
+                    Ok(()) => Ok(SendOutcome::Fallback),
+                    Err(error) => Err(error),
+                },
+                None => Err(SendError::Network(error)),
+            },
+            Err(error) => Err(error),
+        }
+    }
 
-    #[test]
-    fn builder_requires_auth_method() {
-        let builder_result = Client::builder().account_sid("".into()).build();
-        assert_eq!(Err(ClientBuilderError::NoAuthMethodSet), builder_result);
+    /// This function sends a `Message` scheduled with
+    /// `MessageBuilder::scheduled_at`, returning a `ScheduledMessage` that can
+    /// be used to cancel it before it's sent. Messages that aren't scheduled
+    /// fail with a `SendError::NotScheduled` error, without contacting Twilio.
+    ///
+    /// Unlike with `send_message`, the `Client`'s fallback isn't used, as a
+    /// message it sent couldn't be canceled.
+    pub async fn send_scheduled(
+        &self,
+        message: &Message<'_>,
+    ) -> Result<ScheduledMessage, SendError> {
+        let scheduled_at = match message.scheduled_at {
+            Some(scheduled_at) => scheduled_at,
+            None => return Err(SendError::NotScheduled),
+        };
+        self.check_segments(message.body.as_deref())?;
+        let params = message_params(
+            message,
+            self.default_content_sid.as_deref(),
+            self.default_country_code.as_deref(),
+        )?;
+        let message_response = self.post_message(&params, &message.headers).await?;
+        Ok(ScheduledMessage::new(message_response.sid, scheduled_at))
     }
 
-    #[test]
-    fn builder_requires_sid() {
-        let builder_result = Client::builder().auth_token("".into()).build();
-        assert_eq!(Err(ClientBuilderError::NoAccountSidSet), builder_result);
+    /// This function sends a `Message`, returning a record of exactly what was
+    /// sent and what Twilio responded with, for audit trails.
+    ///
+    /// If the `Client` is set to redact audit records, the phone numbers in the
+    /// record are masked the same way as in logs, and the body and Content
+    /// Variables are left out entirely, both from the recorded params and
+    /// from Twilio's recorded response.
+    ///
+    /// # Return value
+    ///
+    /// Unlike `send_message`, this returns `Ok` whenever Twilio responded,
+    /// even if the message was rejected, so that failed sends are recorded
+    /// too. Check `AuditRecord::response_status` to see how the send went.
+    pub async fn send_message_audited(
+        &self,
+        message: &Message<'_>,
+    ) -> Result<AuditRecord, SendError> {
+        self.check_segments(message.body.as_deref())?;
+        let mut params = message_params(
+            message,
+            self.default_content_sid.as_deref(),
+            self.default_country_code.as_deref(),
+        )?;
+        if let Some(from) = self.pool_sender(&params, 0) {
+            params.push(("From", Cow::Borrowed(from)));
+        }
+        let twilio_response = self.post_message_raw(&params, &message.headers).await?;
+        let response_status = twilio_response.status().as_u16();
+        let response_body = match twilio_response.text().await {
+            Ok(response_body) => response_body,
+            Err(error) => return Err(SendError::Network(error)),
+        };
+        // the SID is only there if the message was created
+        let sid = serde_json::from_str::<MessageResponse>(&response_body)
+            .ok()
+            .map(|created| created.sid);
+        let (request_params, response_body) = if self.redact_audit_records {
+            let response_body = redact_response_body(&response_body, &params);
+            (redact_audit_params(&params), response_body)
+        } else {
+            (params, response_body)
+        };
+        Ok(AuditRecord {
+            request_params: request_params
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into_owned()))
+                .collect(),
+            request_url: self.messages_url(),
+            response_body,
+            response_status,
+            sid,
+        })
+    }
+
+    /// This function sends a `Message` whose body is too long for a single
+    /// part as several messages, with at most `concurrency` requests in flight
+    /// at once.
+    ///
+    /// The body is split on word boundaries into parts of at most
+    /// `max_chars_per_part` characters. If `numbered` is set, each part is
+    /// prefixed with its position, like `(1/3) `, which helps the recipient
+    /// read them in order if they arrive out of order. The prefix counts
+    /// towards `max_chars_per_part`, so if it wouldn't leave room for any of
+    /// the body, the parts aren't numbered. Any media is only sent
+    /// with the first part. A message without a body is sent as-is. Each part
+    /// is held to `ClientBuilder::max_segments_per_message` on its own.
+    ///
+    /// # Return value
+    ///
+    /// The results are returned in the same order as the parts. As with
+    /// `send_message`, an `Ok` only means that Twilio accepted the part.
+    pub async fn send_split(
+        &self,
+        message: &Message<'_>,
+        max_chars_per_part: usize,
+        numbered: bool,
+        concurrency: usize,
+    ) -> Vec<Result<MessageResponse, SendError>> {
+        let params = match message_params(
+            message,
+            self.default_content_sid.as_deref(),
+            self.default_country_code.as_deref(),
+        ) {
+            Ok(params) => params,
+            Err(error) => return vec![Err(error)],
+        };
+        let body = match message.body.as_deref() {
+            Some(body) => body,
+            None => return vec![self.post_message(&params, &message.headers).await],
+        };
+        let parts = message::split_body(body, max_chars_per_part, numbered);
+        stream::iter(parts.into_iter().enumerate())
+            .map(|(index, part)| {
+                // each part is limited like a message of its own
+                let segments_result = self.check_segments(Some(&part));
+                let mut part = Some(part);
+                let part_params: Vec<_> = params
+                    .iter()
+                    .filter(|(key, _)| index == 0 || *key != "MediaUrl")
+                    .map(|(key, value)| match *key {
+                        "Body" => (*key, part.take().unwrap()),
+                        _ => (*key, Cow::Borrowed(value.as_ref())),
+                    })
+                    .collect();
+                async move {
+                    segments_result?;
+                    self.post_message(&part_params, &message.headers).await
+                }
+            })
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    /// This function sends the same content from the same sender to many
+    /// recipients, with at most `concurrency` requests in flight at once.
+    ///
+    /// The parameters shared by every message are only assembled once, so this
+    /// is cheaper than building a `Message` per recipient when broadcasting to
+    /// a large number of recipients. The `Client`'s defaults, like
+    /// `ClientBuilder::default_country_code` and
+    /// `ClientBuilder::max_segments_per_message`, apply the same as with
+    /// `send_message`. The options only a `Message` has, like a status
+    /// callback, a schedule, or media, can't be set here; use `send_many` for
+    /// those.
+    ///
+    /// # Return value
+    ///
+    /// The results are returned in the same order as `recipients`. As with
+    /// `send_message`, an `Ok` only means that Twilio accepted the message.
+    pub async fn send_same_to_many(
+        &self,
+        content: Content<'_>,
+        from: &str,
+        recipients: &[&str],
+        concurrency: usize,
+    ) -> Vec<Result<MessageResponse, SendError>> {
+        // first, the params every message has in common. the template has no
+        // destination, so each message only needs its own added
+        let template = content.to_message(from);
+        // every message would fail the same way, so let them all know
+        if let Err(SendError::TooManySegments { max, segments }) =
+            self.check_segments(template.body.as_deref())
+        {
+            return recipients
+                .iter()
+                .map(|_| Err(SendError::TooManySegments { max, segments }))
+                .collect();
+        }
+        let shared_params =
+            match message_params(&template, self.default_content_sid.as_deref(), None) {
+                Ok(shared_params) => shared_params,
+                Err(SendError::Json(error)) => {
+                    return recipients
+                        .iter()
+                        .map(|_| Err(SendError::Json(ser::Error::custom(&error))))
+                        .collect();
+                }
+                // the only other error is a missing Content SID, and a
+                // `Content` always has a body or a Content SID
+                Err(_) => {
+                    return recipients
+                        .iter()
+                        .map(|_| Err(SendError::NoContentSid))
+                        .collect()
+                }
+            };
+        stream::iter(recipients)
+            .map(|to| {
+                let params: Vec<_> = shared_params
+                    .iter()
+                    .map(|(key, value)| match *key {
+                        "To" => (
+                            *key,
+                            with_country_code(to, self.default_country_code.as_deref()),
+                        ),
+                        _ => (*key, Cow::Borrowed(value.as_ref())),
+                    })
+                    .collect();
+                async move { self.post_message(&params, &[]).await }
+            })
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    /// This function returns the username and password to use for HTTP basic
+    /// authentication with Twilio.
+    fn basic_auth(&self) -> (&str, &str) {
+        match &self.auth {
+            AuthMethod::AccountAuthToken(token) => (&self.account_sid, token),
+            AuthMethod::APIKey(key, secret) => (key, secret),
+        }
+    }
+
+    /// This function checks that `body` doesn't need more segments than the
+    /// `Client` allows, if it has a limit. Messages without a body are fine.
+    fn check_segments(&self, body: Option<&str>) -> Result<(), SendError> {
+        let max = match self.max_segments_per_message {
+            Some(max) => max,
+            None => return Ok(()),
+        };
+        match body.map(message::segments) {
+            Some(segments) if segments > max => Err(SendError::TooManySegments { max, segments }),
+            _ => Ok(()),
+        }
+    }
+
+    /// This function fetches a message every `poll_interval` until it has a
+    /// final status, like `delivered` or `failed`, and returns it. This is
+    /// useful for waiting to find out whether a message you sent arrived. See
+    /// `MessageStatus::is_final` for which statuses are final.
+    ///
+    /// If the message doesn't have a final status within `timeout`, this
+    /// returns a `SendError::DeliveryTimeout` with the last status it had.
+    /// Errors fetching the message are returned as soon as they happen.
+    pub async fn wait_for_delivery(
+        &self,
+        message_sid: &str,
+        poll_interval: Duration,
+        timeout: Duration,
+    ) -> Result<MessageResponse, SendError> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let message = self.fetch_message(message_sid).await?;
+            if message.status.is_final() {
+                return Ok(message);
+            }
+            // there's no point waiting if we won't get to check again
+            if tokio::time::Instant::now() + poll_interval > deadline {
+                return Err(SendError::DeliveryTimeout {
+                    status: message.status,
+                });
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
+    /// This function returns a stream of a message's statuses, fetching the
+    /// message every `poll_interval`. Each status is only yielded once, when
+    /// it changes, and the stream ends after a final status, like `delivered`
+    /// or `failed`. This is useful for showing a message's progress as it
+    /// happens. See `MessageStatus::is_final` for which statuses are final.
+    ///
+    /// If fetching the message fails, the error is yielded and the stream
+    /// ends. Use `wait_for_delivery` instead if you only need the final
+    /// status.
+    pub fn watch_message<'a>(
+        &'a self,
+        message_sid: &'a str,
+        poll_interval: Duration,
+    ) -> impl Stream<Item = Result<MessageStatus, SendError>> + 'a {
+        // the state is the last status yielded, and whether we're done
+        stream::unfold((None, false), move |(last_status, done)| async move {
+            if done {
+                return None;
+            }
+            // the first fetch happens right away
+            let mut first_fetch = last_status.is_none();
+            loop {
+                if !first_fetch {
+                    tokio::time::sleep(poll_interval).await;
+                }
+                first_fetch = false;
+                match self.fetch_message(message_sid).await {
+                    Ok(message) if Some(message.status) == last_status => continue,
+                    Ok(message) => {
+                        let status = message.status;
+                        return Some((Ok(status), (Some(status), status.is_final())));
+                    }
+                    Err(error) => return Some((Err(error), (last_status, true))),
+                }
+            }
+        })
+    }
+
+    /// This function fetches the resource at `url` from Twilio and parses it,
+    /// waiting for at most `timeout`, if it's set.
+    async fn fetch<T: DeserializeOwned>(
+        &self,
+        url: String,
+        timeout: Option<Duration>,
+    ) -> Result<T, SendError> {
+        let (auth_user, auth_pass) = self.basic_auth();
+        let mut request = self.http.get(url).basic_auth(auth_user, Some(auth_pass));
+        if let Some(timeout) = timeout {
+            request = request.timeout(timeout);
+        }
+        let twilio_result = request.send().await;
+        let twilio_response = match twilio_result {
+            Ok(response) => response,
+            Err(error) => return Err(SendError::Network(error)),
+        };
+        if !twilio_response.status().is_success() {
+            return Err(twilio_error(twilio_response).await);
+        }
+        let body = match twilio_response.text().await {
+            Ok(body) => body,
+            Err(error) => return Err(SendError::Network(error)),
+        };
+        match serde_json::from_str(&body) {
+            Ok(resource) => Ok(resource),
+            Err(error) => Err(SendError::Json(error)),
+        }
+    }
+
+    /// This function returns the URL of the account, which the URLs of the
+    /// account's resources start with.
+    fn account_url(&self) -> String {
+        format!("{}/Accounts/{}", self.base_url, self.account_sid)
+    }
+
+    /// This function turns a URI Twilio returned, which is relative to the
+    /// host of its API, into a URL.
+    fn resolve_uri(&self, uri: &str) -> String {
+        // the uri starts with the api version, which the base url ends with
+        let host_end = match self.base_url.find("://") {
+            Some(scheme_end) => match self.base_url[scheme_end + 3..].find('/') {
+                Some(path_start) => scheme_end + 3 + path_start,
+                None => self.base_url.len(),
+            },
+            None => 0,
+        };
+        format!("{}{uri}", &self.base_url[..host_end])
+    }
+
+    /// This function returns the URL of a phone number in Twilio Lookup, with
+    /// the given fields requested.
+    fn lookup_url(&self, phone_number: &str, fields: &[LookupField]) -> String {
+        // form encoding turns spaces into pluses, which aren't spaces in a
+        // path. any plus in the number itself is already escaped
+        let phone_number = form_urlencoded::byte_serialize(phone_number.as_bytes())
+            .collect::<String>()
+            .replace('+', "%20");
+        let mut url = format!(
+            "{}/PhoneNumbers/{phone_number}",
+            regional_url("lookups", &self.edge, &self.region)
+        );
+        if !fields.is_empty() {
+            let fields: Vec<_> = fields.iter().map(LookupField::as_str).collect();
+            url.push_str("?Fields=");
+            url.push_str(&fields.join(","));
+        }
+        url
+    }
+
+    /// This function returns the URL of one of the account's messages.
+    fn message_url(&self, message_sid: &str) -> String {
+        format!("{}/Messages/{message_sid}.json", self.account_url())
+    }
+
+    /// This function returns the URL of the account's Messages resource.
+    fn messages_url(&self) -> String {
+        format!("{}/Messages.json", self.account_url())
+    }
+
+    /// This function sends a request to create a message with the given params
+    /// and extra headers, retrying transient failures if the `Client` is set to.
+    async fn post_message(
+        &self,
+        params: &[(&str, Cow<'_, str>)],
+        headers: &[(Cow<'_, str>, Cow<'_, str>)],
+    ) -> Result<MessageResponse, SendError> {
+        let mut attempt = 0;
+        let twilio_response = loop {
+            // messages without a sender of their own take turns sending from
+            // the pool, if there is one
+            let pooled_params: Vec<_>;
+            let attempt_params = match self.pool_sender(params, attempt) {
+                Some(from) => {
+                    pooled_params = params
+                        .iter()
+                        .map(|(key, value)| (*key, Cow::Borrowed(value.as_ref())))
+                        .chain([("From", Cow::Borrowed(from))])
+                        .collect();
+                    &pooled_params
+                }
+                None => params,
+            };
+            let (error, retry_after) = match self.post_message_raw(attempt_params, headers).await {
+                Ok(response) if response.status().is_success() => break response,
+                Ok(response) => {
+                    let retry_after = retry_after(response.headers());
+                    (twilio_error(response).await, retry_after)
+                }
+                Err(error) => (error, None),
+            };
+            if attempt >= self.max_retries || !is_transient(&error) {
+                return Err(error);
+            }
+            let delay = self.retry_delay(attempt, retry_after);
+            #[cfg(feature = "tracing")]
+            tracing::debug!(%error, ?delay, attempt, "retrying message request");
+            if let Some(on_retry) = &self.on_retry {
+                (on_retry.0)(attempt + 1, &error);
+            }
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        };
+        let message_response = MessageResponse::from_response(twilio_response).await?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(sid = %message_response.sid, "message created");
+        Ok(message_response)
+    }
+
+    /// This function waits for a send permit if the `Client` caps the number
+    /// of messages it sends at once. The permit should be held until the
+    /// response has been received.
+    async fn send_permit(&self) -> Option<SemaphorePermit<'_>> {
+        match &self.send_permits {
+            Some(send_permits) => send_permits.acquire().await.ok(),
+            None => None,
+        }
+    }
+
+    /// This function prepares a request that sends or changes a message by
+    /// authenticating it and applying the send timeout, if one is set.
+    fn send_request(&self, request: RequestBuilder) -> RequestBuilder {
+        let (auth_user, auth_pass) = self.basic_auth();
+        let request = request.basic_auth(auth_user, Some(auth_pass));
+        // a send timeout replaces the client's timeout for this request
+        match self.send_timeout {
+            Some(send_timeout) => request.timeout(send_timeout),
+            None => request,
+        }
+    }
+
+    /// This function returns the number from the `Client`'s pool of senders to
+    /// send attempt `attempt` from, if there's a pool and `params` don't have
+    /// a sender of their own.
+    fn pool_sender(&self, params: &[(&str, Cow<'_, str>)], attempt: u32) -> Option<&str> {
+        if self.from_pool.is_empty() || params.iter().any(|(key, _)| *key == "From") {
+            return None;
+        }
+        let index = attempt as usize % self.from_pool.len();
+        Some(&self.from_pool[index])
+    }
+
+    /// This function returns how long to wait before retrying a request that
+    /// has failed `attempt + 1` times.
+    fn retry_delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        // if twilio told us how long to wait, we'll do that. otherwise, wait
+        // a random part of a window that doubles after each failed attempt, so
+        // that clients that failed together don't all retry together
+        if let Some(retry_after) = retry_after {
+            return retry_after;
+        }
+        let window = self
+            .retry_base_delay
+            .saturating_mul(2u32.saturating_pow(attempt));
+        let part = (self.retry_rng.0)().clamp(0.0, 1.0);
+        Duration::try_from_secs_f64(window.as_secs_f64() * part).unwrap_or(window)
+    }
+
+    /// This function sends a request to create a message with the given params
+    /// and extra headers, returning Twilio's response whatever its status.
+    async fn post_message_raw(
+        &self,
+        params: &[(&str, Cow<'_, str>)],
+        headers: &[(Cow<'_, str>, Cow<'_, str>)],
+    ) -> Result<reqwest::Response, SendError> {
+        // only the param names are logged unless the client is set to log the
+        // (redacted) values too
+        #[cfg(feature = "tracing")]
+        if self.log_requests {
+            tracing::debug!(params = ?redact_params(params), "sending message request");
+        } else {
+            let param_names: Vec<_> = params.iter().map(|(name, _)| *name).collect();
+            tracing::debug!(?param_names, "sending message request");
+        }
+        let _permit = self.send_permit().await;
+        let url = self.messages_url();
+        let request = || {
+            let mut request = self.http.post(&url);
+            for (name, value) in headers {
+                request = request.header(name.as_ref(), value.as_ref());
+            }
+            self.send_request(request)
+        };
+        #[cfg(feature = "gzip")]
+        let twilio_result = if self.gzip_request {
+            use reqwest::{
+                header::{CONTENT_ENCODING, CONTENT_TYPE},
+                StatusCode,
+            };
+
+            let gzip_result = request()
+                .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .header(CONTENT_ENCODING, "gzip")
+                .body(gzip_form(params)?)
+                .send()
+                .await;
+            match gzip_result {
+                // not every twilio endpoint accepts compressed bodies, so if
+                // this one doesn't, we'll try again without compression
+                Ok(response) if response.status() == StatusCode::UNSUPPORTED_MEDIA_TYPE => {
+                    request().form(params).send().await
+                }
+                gzip_result => gzip_result,
+            }
+        } else {
+            request().form(params).send().await
+        };
+        #[cfg(not(feature = "gzip"))]
+        let twilio_result = request().form(params).send().await;
+        match twilio_result {
+            Ok(response) => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(status = response.status().as_u16(), "received response");
+                Ok(response)
+            }
+            Err(error) => Err(SendError::Network(error)),
+        }
+    }
+}
+
+/// The body of Twilio's response to an unsuccessful request, as far as we need
+/// it.
+#[derive(Default, Deserialize)]
+struct TwilioErrorBody {
+    code: Option<u32>,
+    message: Option<String>,
+    more_info: Option<String>,
+}
+
+/// This function turns Twilio's response to an unsuccessful request into a
+/// `SendError::Twilio` error.
+pub(crate) async fn twilio_error(response: reqwest::Response) -> SendError {
+    let status = response.status().as_u16();
+    // the details are nice to have, so we won't fail if we can't read them
+    let body = response.text().await.unwrap_or_default();
+    twilio_error_from_body(status, &body)
+}
+
+/// This function creates a `SendError::Twilio` error from the status and body
+/// of Twilio's response. Bodies that aren't Twilio errors are ignored.
+fn twilio_error_from_body(status: u16, body: &str) -> SendError {
+    let error_body: TwilioErrorBody = serde_json::from_str(body).unwrap_or_default();
+    SendError::Twilio {
+        code: error_body.code,
+        message: error_body.message,
+        more_info: error_body.more_info,
+        status,
+    }
+}
+
+/// This function returns whether `error` is likely to go away if the request
+/// is sent again: Twilio couldn't be reached or didn't respond in time, was
+/// rate limiting us, or had a problem of its own. Other network errors, like
+/// invalid URLs, would just happen again.
+fn is_transient(error: &SendError) -> bool {
+    match error {
+        SendError::Network(error) => error.is_connect() || error.is_timeout(),
+        SendError::Twilio { status, .. } => matches!(status, 429 | 500 | 502 | 503),
+        _ => false,
+    }
+}
+
+/// This function returns a random number from 0 to 1, for spreading out
+/// retries. It doesn't need to be unpredictable, so rather than depending on a
+/// random number generator, we use the random keys the standard library seeds
+/// its hash maps with.
+fn random_fraction() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    // the top 53 bits fill an f64's mantissa
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// This function returns how long Twilio asked us to wait before sending
+/// another request, if its response had a `Retry-After` header in seconds.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let retry_after = headers.get(RETRY_AFTER)?.to_str().ok()?;
+    match retry_after.trim().parse() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => None,
+    }
+}
+
+/// This function form-encodes and gzip-compresses the given params.
+#[cfg(feature = "gzip")]
+fn gzip_form(params: &[(&str, Cow<'_, str>)]) -> Result<Vec<u8>, SendError> {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+
+    let form = form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(params)
+        .finish();
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(form.as_bytes())?;
+    Ok(encoder.finish()?)
+}
+
+/// This function quotes `value` so that a POSIX shell reads it as one word,
+/// as it is.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// This function redacts the given params so that they can be logged or kept
+/// in audit records: phone numbers are masked, bodies are truncated, and
+/// Content Variables are hidden.
+fn redact_params<'p>(params: &'p [(&str, Cow<'_, str>)]) -> Vec<(&'p str, Cow<'p, str>)> {
+    const MAX_BODY_CHARS: usize = 16;
+    params
+        .iter()
+        .map(|(key, value)| {
+            let value = match *key {
+                "To" | "From" => Cow::Owned(message::mask_number(value)),
+                "Body" => match value.char_indices().nth(MAX_BODY_CHARS) {
+                    Some((index, _)) => Cow::Owned(format!("{}…", &value[..index])),
+                    None => Cow::Borrowed(value.as_ref()),
+                },
+                "ContentVariables" => Cow::Borrowed("<redacted>"),
+                _ => Cow::Borrowed(value.as_ref()),
+            };
+            (*key, value)
+        })
+        .collect()
+}
+
+/// This function redacts the params recorded in an `AuditRecord`. They're
+/// redacted like they are for logs, except that none of the body is kept, as
+/// audit records tend to be kept for a long time.
+fn redact_audit_params<'p>(params: &'p [(&str, Cow<'_, str>)]) -> Vec<(&'p str, Cow<'p, str>)> {
+    redact_params(params)
+        .into_iter()
+        .map(|(key, value)| match key {
+            "Body" => (key, Cow::Borrowed("<redacted>")),
+            _ => (key, value),
+        })
+        .collect()
+}
+
+/// This function redacts Twilio's response to the message request made with
+/// `params`, to match `redact_audit_params`: the numbers are masked and the
+/// body is left out.
+fn redact_response_body(response_body: &str, params: &[(&str, Cow<'_, str>)]) -> String {
+    // twilio may mention the numbers anywhere, like in an error message, so
+    // they're masked wherever they appear
+    let numbers: Vec<_> = params
+        .iter()
+        .filter(|(key, value)| matches!(*key, "To" | "From") && !value.is_empty())
+        .map(|(_, value)| (value.as_ref(), message::mask_number(value)))
+        .collect();
+    let mask_numbers = |text: &str| {
+        numbers
+            .iter()
+            .fold(text.to_string(), |text, (number, masked)| {
+                text.replace(number, masked)
+            })
+    };
+    let mut json: serde_json::Value = match serde_json::from_str(response_body) {
+        Ok(json) => json,
+        // twilio always responds with json, but just in case
+        Err(_) => return mask_numbers(response_body),
+    };
+    if let Some(fields) = json.as_object_mut() {
+        for (key, value) in fields.iter_mut() {
+            if let serde_json::Value::String(text) = value {
+                *text = match key.as_str() {
+                    "body" => "<redacted>".into(),
+                    // twilio may have formatted the numbers differently
+                    "from" | "to" => message::mask_number(text),
+                    _ => mask_numbers(text),
+                };
+            }
+        }
+    }
+    json.to_string()
+}
+
+/// This function adds `default_country_code` to `to` if it looks like a
+/// national number without a country code. See
+/// `ClientBuilder::default_country_code` for the details.
+fn with_country_code<'t>(to: &'t str, default_country_code: Option<&str>) -> Cow<'t, str> {
+    let default_country_code = match default_country_code {
+        Some(default_country_code) => default_country_code,
+        None => return Cow::Borrowed(to),
+    };
+    // keep any channel prefix, like `whatsapp:`, in front of the number
+    let (prefix, number) = match to.rfind(':') {
+        Some(index) => to.split_at(index + 1),
+        None => ("", to),
+    };
+    if number.starts_with('+') || number.chars().any(char::is_alphabetic) {
+        return Cow::Borrowed(to);
+    }
+    let digits: String = number.chars().filter(char::is_ascii_digit).collect();
+    if digits.is_empty() {
+        return Cow::Borrowed(to);
+    }
+    let national = digits.strip_prefix('0').unwrap_or(&digits);
+    Cow::Owned(format!("{prefix}{default_country_code}{national}"))
+}
+
+/// This function assembles the form params Twilio expects for a `Message`.
+///
+/// If the message only has Content Variables, `default_content_sid` is used as
+/// its Content SID. `default_country_code` is added to the destination if it
+/// doesn't have a country code.
+fn message_params<'m>(
+    message: &'m Message<'_>,
+    default_content_sid: Option<&'m str>,
+    default_country_code: Option<&str>,
+) -> Result<Vec<(&'static str, Cow<'m, str>)>, SendError> {
+    // if the message has no content of its own, it's relying on the default
+    // content template
+    let content_sid = match message.content_sid.as_deref() {
+        Some(content_sid) => Some(content_sid),
+        None if message.body.is_none() && message.media_urls.is_none() => match default_content_sid
+        {
+            Some(default_content_sid) => Some(default_content_sid),
+            None => return Err(SendError::NoContentSid),
+        },
+        None => None,
+    };
+    // in order to avoid having our params map reallocate every time we push
+    // one, we're going to count the number we need, then allocate once.
+    // we know for sure we have one: the message destination, so we'll start
+    // with one.
+    let mut num_params = 1;
+    // now let's sort out the sender items
+    if message.from.is_some() {
+        num_params += 1;
+    }
+    if message.messaging_service_sid.is_some() {
+        num_params += 1;
+    }
+    // now, the content items. i'll save the media URL(s) for last because
+    // they're a bit more tricky
+    if message.body.is_some() {
+        num_params += 1;
+    }
+    if content_sid.is_some() {
+        num_params += 1;
+    }
+    if message.content_variables.is_some() {
+        num_params += 1;
+    }
+    // then the callback items
+    if message.status_callback.is_some() {
+        num_params += 1;
+    }
+    if message.status_callback_method.is_some() {
+        num_params += 1;
+    }
+    // scheduling takes two: the type and the time
+    if message.scheduled_at.is_some() {
+        num_params += 2;
+    }
+    // and finally, the delivery options
+    if message.max_price.is_some() {
+        num_params += 1;
+    }
+    if message.shorten_urls.is_some() {
+        num_params += 1;
+    }
+    if message.smart_encoding.is_some() {
+        num_params += 1;
+    }
+    if message.validity_period.is_some() {
+        num_params += 1;
+    }
+    if let Some(media_urls) = &message.media_urls {
+        // like i said
+        num_params += media_urls.len();
+    }
+    // now that we know how many params we need, let's create our params
+    let mut params = Vec::with_capacity(num_params);
+    params.push(("To", with_country_code(&message.to, default_country_code)));
+    if let Some(from) = message.from.as_deref() {
+        params.push(("From", Cow::Borrowed(from)));
+    }
+    if let Some(messaging_service_sid) = message.messaging_service_sid.as_deref() {
+        params.push(("MessagingServiceSid", Cow::Borrowed(messaging_service_sid)));
+    }
+    if let Some(body) = message.body.as_deref() {
+        params.push(("Body", Cow::Borrowed(body)));
+    }
+    if let Some(content_sid) = content_sid {
+        params.push(("ContentSid", Cow::Borrowed(content_sid)));
+    }
+    if let Some(content_variables) = &message.content_variables {
+        let content_variables_json = match serde_json::to_string(&content_variables) {
+            Ok(content_variables_json) => content_variables_json,
+            Err(error) => return Err(SendError::Json(error)),
+        };
+        params.push(("ContentVariables", Cow::Owned(content_variables_json)));
+    }
+    if let Some(status_callback) = message.status_callback.as_deref() {
+        params.push(("StatusCallback", Cow::Borrowed(status_callback)));
+    }
+    if let Some(status_callback_method) = message.status_callback_method {
+        params.push((
+            "StatusCallbackMethod",
+            Cow::Borrowed(status_callback_method.as_str()),
+        ));
+    }
+    if let Some(scheduled_at) = message.scheduled_at {
+        params.push(("ScheduleType", Cow::Borrowed("fixed")));
+        params.push((
+            "SendAt",
+            Cow::Owned(scheduled_at.to_rfc3339_opts(SecondsFormat::Secs, true)),
+        ));
+    }
+    if let Some(max_price) = message.max_price {
+        params.push(("MaxPrice", Cow::Owned(max_price.to_string())));
+    }
+    if let Some(shorten_urls) = message.shorten_urls {
+        params.push(("ShortenUrls", Cow::Owned(shorten_urls.to_string())));
+    }
+    if let Some(smart_encoding) = message.smart_encoding {
+        params.push(("SmartEncoded", Cow::Owned(smart_encoding.to_string())));
+    }
+    if let Some(validity_period) = message.validity_period {
+        params.push(("ValidityPeriod", Cow::Owned(validity_period.to_string())));
+    }
+    if let Some(media_urls) = &message.media_urls {
+        for media_url in media_urls {
+            params.push(("MediaUrl", Cow::Borrowed(media_url.as_ref())));
+        }
+    }
+    Ok(params)
+}
+
+/// The `ClientBuilderError` enum represents the various types of errors that
+/// can arise when attempting to build a `ClientBuilder`.
+#[derive(Debug, thiserror::Error, PartialEq)]
+#[non_exhaustive]
+pub enum ClientBuilderError {
+    /// This error occurs when you attempt to build a `ClientBuilder` without
+    /// setting an account SID.
+    #[error("no account SID set in builder")]
+    NoAccountSidSet,
+    /// This error occurs when you attempt to build a `ClientBuilder` without
+    /// setting an authentication method, either the account's auth token or an
+    /// API key and secret.
+    #[error("no auth method set in builder")]
+    NoAuthMethodSet,
+    /// This error occurs when the underlying HTTP client couldn't be created.
+    /// The `reqwest` error message is contained in this error.
+    #[error("couldn't create HTTP client: {0}")]
+    HttpClient(String),
+    /// This error occurs when `Client::from_env` can't read an environment
+    /// variable it needs. The name of the variable is contained in this error.
+    #[error("environment variable {0} not set")]
+    MissingEnvVar(&'static str),
+    /// This error occurs when the runtime a blocking `Client` runs its
+    /// requests on couldn't be created. The `tokio` error message is contained
+    /// in this error.
+    #[cfg(feature = "blocking")]
+    #[error("couldn't create runtime: {0}")]
+    Runtime(String),
+}
+
+/// This function returns the value of the environment variable `name`, or a
+/// `ClientBuilderError::MissingEnvVar` if it isn't set or isn't unicode.
+fn env_var(name: &'static str) -> Result<String, ClientBuilderError> {
+    match env::var(name) {
+        Ok(value) => Ok(value),
+        Err(_) => Err(ClientBuilderError::MissingEnvVar(name)),
+    }
+}
+
+/// This function returns the URL of a Twilio product's API, like `api` or
+/// `content`, at the given edge location and region. Twilio's default region
+/// is `us1`, so that's used if only an edge is given.
+fn regional_url(product: &str, edge: &Option<String>, region: &Option<String>) -> String {
+    let version = match product {
+        "content" => "v1",
+        "lookups" => "v2",
+        _ => "2010-04-01",
+    };
+    let host = match (edge, region) {
+        (Some(edge), Some(region)) => format!("{product}.{edge}.{region}.twilio.com"),
+        (Some(edge), None) => format!("{product}.{edge}.us1.twilio.com"),
+        (None, Some(region)) => format!("{product}.{region}.twilio.com"),
+        (None, None) => format!("{product}.twilio.com"),
+    };
+    format!("https://{host}/{version}")
+}
+
+/// The `ClientBuilder` struct is used to create a `Client`.
+pub struct ClientBuilder {
+    account_sid: Option<String>,
+    auth: Option<AuthMethod>,
+    base_url: Option<String>,
+    default_content_sid: Option<String>,
+    default_country_code: Option<String>,
+    edge: Option<String>,
+    fallback: Option<Arc<dyn FallbackSender>>,
+    fetch_timeout: Option<Duration>,
+    from_pool: Vec<String>,
+    #[cfg(feature = "gzip")]
+    gzip_request: bool,
+    list_timeout: Option<Duration>,
+    #[cfg(feature = "tracing")]
+    log_requests: bool,
+    max_concurrent_sends: Option<usize>,
+    max_retries: u32,
+    max_segments_per_message: Option<u32>,
+    on_retry: Option<OnRetry>,
+    redact_audit_records: bool,
+    redirect_policy: Option<Arc<Policy>>,
+    region: Option<String>,
+    retry_base_delay: Duration,
+    retry_rng: RetryRng,
+    send_timeout: Option<Duration>,
+    tcp_nodelay: bool,
+    timeout: Option<Duration>,
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClientBuilder {
+    /// This function creates a `ClientBuilder`.
+    pub fn new() -> Self {
+        ClientBuilder {
+            account_sid: None,
+            auth: None,
+            base_url: None,
+            default_content_sid: None,
+            default_country_code: None,
+            edge: None,
+            fallback: None,
+            fetch_timeout: None,
+            from_pool: Vec::new(),
+            #[cfg(feature = "gzip")]
+            gzip_request: false,
+            list_timeout: None,
+            #[cfg(feature = "tracing")]
+            log_requests: false,
+            max_concurrent_sends: None,
+            max_retries: 0,
+            max_segments_per_message: None,
+            on_retry: None,
+            redact_audit_records: false,
+            redirect_policy: None,
+            region: None,
+            retry_base_delay: Duration::from_millis(500),
+            retry_rng: Hook(Arc::new(random_fraction)),
+            send_timeout: None,
+            tcp_nodelay: true,
+            timeout: None,
+        }
+    }
+
+    /// This function validates the builder chain and returns a `Client` that
+    /// you can then use to interact with Twilio.
+    pub fn build(&self) -> Result<Client, ClientBuilderError> {
+        if self.account_sid.is_none() {
+            return Err(ClientBuilderError::NoAccountSidSet);
+        }
+        let account_sid = self.account_sid.clone().unwrap();
+        if self.auth.is_none() {
+            return Err(ClientBuilderError::NoAuthMethodSet);
+        }
+        let auth = self.auth.clone().unwrap();
+        // twilio doesn't redirect, so unless we've been told otherwise we
+        // won't follow any redirects
+        let redirect_policy = match &self.redirect_policy {
+            Some(policy) => {
+                let policy = Arc::clone(policy);
+                Policy::custom(move |attempt| policy.redirect(attempt))
+            }
+            None => Policy::none(),
+        };
+        let mut http = reqwest::Client::builder()
+            .redirect(redirect_policy)
+            .tcp_nodelay(self.tcp_nodelay);
+        if let Some(timeout) = self.timeout {
+            http = http.timeout(timeout);
+        }
+        let http = match http.build() {
+            Ok(http) => http,
+            Err(error) => return Err(ClientBuilderError::HttpClient(error.to_string())),
+        };
+        Ok(Client {
+            account_sid,
+            auth,
+            base_url: match &self.base_url {
+                Some(base_url) => base_url.clone(),
+                None => regional_url("api", &self.edge, &self.region),
+            },
+            default_content_sid: self.default_content_sid.clone(),
+            default_country_code: self.default_country_code.clone(),
+            edge: self.edge.clone(),
+            fallback: self.fallback.clone(),
+            fetch_timeout: self.fetch_timeout,
+            from_pool: self.from_pool.clone(),
+            #[cfg(feature = "gzip")]
+            gzip_request: self.gzip_request,
+            http,
+            list_timeout: self.list_timeout,
+            #[cfg(feature = "tracing")]
+            log_requests: self.log_requests,
+            max_concurrent_sends: self.max_concurrent_sends,
+            max_retries: self.max_retries,
+            max_segments_per_message: self.max_segments_per_message,
+            on_retry: self.on_retry.clone(),
+            redact_audit_records: self.redact_audit_records,
+            redirect_policy: self.redirect_policy.clone(),
+            region: self.region.clone(),
+            retry_base_delay: self.retry_base_delay,
+            retry_rng: self.retry_rng.clone(),
+            send_permits: self
+                .max_concurrent_sends
+                .map(|max_concurrent_sends| Arc::new(Semaphore::new(max_concurrent_sends.max(1)))),
+            send_timeout: self.send_timeout,
+            tcp_nodelay: self.tcp_nodelay,
+            timeout: self.timeout,
+        })
+    }
+
+    /// This function validates the builder chain and returns a blocking
+    /// `Client`, for programs that don't use async.
+    #[cfg(feature = "blocking")]
+    pub fn build_blocking(&self) -> Result<crate::blocking::Client, ClientBuilderError> {
+        crate::blocking::Client::new(self.build()?)
+    }
+
+    /// This function sets the account SID to be used by the `Client` when
+    /// interacting with Twilio.
+    pub fn account_sid(&mut self, account_sid: String) -> &mut Self {
+        self.account_sid = Some(account_sid);
+        self
+    }
+
+    /// This function sets the API key to be used to authenticate the `Client`
+    /// with Twilio.
+    pub fn api_key(&mut self, key: String, secret: String) -> &mut Self {
+        self.auth = Some(AuthMethod::APIKey(key, secret));
+        self
+    }
+
+    /// This function sets the account auth token to be used to authenticate the
+    /// `Client` with Twilio.
+    pub fn auth_token(&mut self, token: String) -> &mut Self {
+        self.auth = Some(AuthMethod::AccountAuthToken(token));
+        self
+    }
+
+    /// This function sets the URL of Twilio's API that the `Client` sends its
+    /// requests to, which defaults to `https://api.twilio.com/2010-04-01`. This
+    /// is useful for pointing the `Client` at a mock server in tests. It takes
+    /// precedence over `region` and `edge`.
+    ///
+    /// Content templates are fetched from Twilio's separate Content API, which
+    /// this doesn't change.
+    pub fn base_url(&mut self, base_url: String) -> &mut Self {
+        // the paths we add start with a slash
+        self.base_url = Some(base_url.trim_end_matches('/').into());
+        self
+    }
+
+    /// This function sets the Twilio Content SID used for messages that only
+    /// have Content Variables set. Messages with their own body, media, or
+    /// Content SID ignore it.
+    pub fn default_content_sid(&mut self, default_content_sid: String) -> &mut Self {
+        self.default_content_sid = Some(default_content_sid);
+        self
+    }
+
+    /// This function sets the country code, like `"+1"`, that the `Client`
+    /// adds to destination numbers that don't have one. By default, numbers
+    /// are sent as they are.
+    ///
+    /// A number is treated as lacking a country code if it doesn't start with
+    /// `+` (after any channel prefix like `whatsapp:`) and is made up of only
+    /// digits and formatting characters. Its formatting characters are dropped,
+    /// as is a single leading `0` (the trunk prefix many countries use for
+    /// national numbers), and the country code is added, so with `"+1"`,
+    /// `"(202) 555-0123"` is sent as `"+12025550123"`. Anything containing
+    /// letters is left alone. Note that this also applies to short codes, so
+    /// don't set this if you send to them.
+    pub fn default_country_code(&mut self, default_country_code: &str) -> &mut Self {
+        // let's make sure there's exactly one plus, however it was passed
+        let default_country_code = default_country_code.trim_start_matches('+');
+        self.default_country_code = Some(format!("+{default_country_code}"));
+        self
+    }
+
+    /// This function sets the Twilio edge location, like `sydney`, that the
+    /// `Client` connects to. Connecting to the edge closest to you reduces
+    /// latency. If no region is set with `region`, Twilio's default region,
+    /// `us1`, is used.
+    pub fn edge(&mut self, edge: String) -> &mut Self {
+        self.edge = Some(edge);
+        self
+    }
+
+    /// This function sets a `FallbackSender` that `Client::send_message` uses
+    /// when Twilio can't be reached, that is, when sending fails with a
+    /// `SendError::Network` error after any retries. Messages Twilio rejects
+    /// aren't passed to the fallback. By default, there's no fallback.
+    pub fn fallback(&mut self, fallback: impl FallbackSender + 'static) -> &mut Self {
+        self.fallback = Some(Arc::new(fallback));
+        self
+    }
+
+    /// This function sets how long the `Client` waits for Twilio when fetching
+    /// resources, like with `Client::fetch_account`. If this isn't set, the
+    /// timeout set with `timeout` is used.
+    pub fn fetch_timeout(&mut self, fetch_timeout: Duration) -> &mut Self {
+        self.fetch_timeout = Some(fetch_timeout);
+        self
+    }
+
+    /// This function sets a pool of numbers for the `Client` to send messages
+    /// that don't have a `from` number of its own from, like messages sent
+    /// through a Messaging Service. The first number in the pool is used
+    /// first, and each retry of a transient failure is sent from the next, so
+    /// a number a carrier is filtering doesn't sink the message. See
+    /// `max_retries` for when sends are retried. By default, there's no pool,
+    /// and such messages are sent without a `From` number.
+    pub fn from_pool(&mut self, from_pool: Vec<String>) -> &mut Self {
+        self.from_pool = from_pool;
+        self
+    }
+
+    /// This function sets whether the `Client` compresses the bodies of its
+    /// requests to create messages with gzip. This is off by default.
+    ///
+    /// This can save bandwidth when sending messages with large Content
+    /// Variables or many media URLs. If Twilio rejects a compressed request
+    /// with `415 Unsupported Media Type`, it's sent again uncompressed.
+    ///
+    /// This is only available with the `gzip` feature.
+    #[cfg(feature = "gzip")]
+    pub fn gzip_request(&mut self, gzip_request: bool) -> &mut Self {
+        self.gzip_request = gzip_request;
+        self
+    }
+
+    /// This function sets how long the `Client` waits for Twilio when listing
+    /// resources, like with `Client::list_messages`. If this isn't set, the
+    /// timeout set with `timeout` is used.
+    pub fn list_timeout(&mut self, list_timeout: Duration) -> &mut Self {
+        self.list_timeout = Some(list_timeout);
+        self
+    }
+
+    /// This function sets whether the `Client` logs the params of its requests
+    /// to create messages at the `DEBUG` level. This is off by default, in
+    /// which case only the names of the params are logged.
+    ///
+    /// The params are redacted before being logged: phone numbers are masked
+    /// with `message::mask_number`, bodies are truncated, and Content Variables
+    /// are hidden. Credentials are never logged.
+    #[cfg(feature = "tracing")]
+    pub fn log_requests(&mut self, log_requests: bool) -> &mut Self {
+        self.log_requests = log_requests;
+        self
+    }
+
+    /// This function caps the number of messages the `Client` sends at once.
+    /// Once the cap is reached, further sends wait for one of the in-flight
+    /// requests to finish. There's no cap by default, and a cap of 0 is treated
+    /// as 1.
+    ///
+    /// The cap applies across every task using the `Client`, no matter which
+    /// function is used to send, so sharing one `Client` keeps the whole
+    /// process within Twilio's concurrency limits. Canceling and deleting
+    /// messages count towards the cap too.
+    pub fn max_concurrent_sends(&mut self, max_concurrent_sends: usize) -> &mut Self {
+        self.max_concurrent_sends = Some(max_concurrent_sends);
+        self
+    }
+
+    /// This function sets how many times the `Client` retries sending a message
+    /// after a transient failure: when Twilio can't be reached or doesn't
+    /// respond in time, or responds with `429 Too Many Requests`, `500 Internal
+    /// Server Error`, `502 Bad Gateway`, or `503 Service Unavailable`. Other
+    /// errors fail immediately. This is 0 by default, so failures aren't
+    /// retried.
+    ///
+    /// Before the first retry, the `Client` waits a random time of up to
+    /// `retry_base_delay`, and before each retry after that, up to twice as
+    /// long as the last time. The randomness keeps many clients that failed at
+    /// once from all retrying at once. If Twilio says how long to wait with a
+    /// `Retry-After` header, as it does when rate limiting, the `Client` waits
+    /// that long instead. Use `on_retry` to be told about each retry, and
+    /// `from_pool` to send each retry from a different number.
+    ///
+    /// Note that a message may be sent twice if Twilio received it but didn't
+    /// respond in time.
+    pub fn max_retries(&mut self, max_retries: u32) -> &mut Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// This function sets the most SMS segments a message's body may be sent
+    /// as. Every function of the `Client` that sends a body refuses to send
+    /// messages over the limit with a `SendError::TooManySegments` error,
+    /// without contacting Twilio. `Client::send_split` applies the limit to
+    /// each part, as it sends each part as its own message. By default,
+    /// there's no limit.
+    ///
+    /// This is a last line of defense against unexpectedly expensive messages,
+    /// however their body was set. See `Message::body_segments` for how
+    /// segments are counted. Messages sent with a Twilio Content template, like
+    /// with `Client::send_content_message`, aren't limited, as their body isn't
+    /// known until Twilio fills in the template.
+    pub fn max_segments_per_message(&mut self, max_segments_per_message: u32) -> &mut Self {
+        self.max_segments_per_message = Some(max_segments_per_message);
+        self
+    }
+
+    /// This function sets a function that the `Client` calls before each retry
+    /// of a failed send, like for counting retries in your metrics. It's called
+    /// with the number of the retry, starting at 1, and the error that's being
+    /// retried. See `max_retries` for when sends are retried.
+    pub fn on_retry(
+        &mut self,
+        on_retry: impl Fn(u32, &SendError) + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.on_retry = Some(Hook(Arc::new(on_retry)));
+        self
+    }
+
+    /// This function sets whether the `Client` redacts the records made by
+    /// `Client::send_message_audited`, masking the phone numbers and leaving
+    /// out the body. This is off by default, so that the record shows exactly
+    /// what was sent and received.
+    pub fn redact_audit_records(&mut self, redact_audit_records: bool) -> &mut Self {
+        self.redact_audit_records = redact_audit_records;
+        self
+    }
+
+    /// This function sets the redirect policy used by the `Client` when
+    /// communicating with Twilio. By default, redirects aren't followed.
+    ///
+    /// # Security
+    ///
+    /// Twilio doesn't redirect API requests, so a redirect can only come from
+    /// something between you and Twilio, such as a proxy. Following it re-sends
+    /// the request, including your message and, when the host doesn't change,
+    /// the `Authorization` header containing your credentials, to wherever the
+    /// redirect points. Only set a policy that follows redirects if you trust
+    /// every hop between you and Twilio.
+    pub fn redirect_policy(&mut self, policy: Policy) -> &mut Self {
+        self.redirect_policy = Some(Arc::new(policy));
+        self
+    }
+
+    /// This function sets the Twilio region, like `au1`, whose API the `Client`
+    /// uses, which keeps data in that region. This is usually set along with
+    /// `edge`. By default, the `Client` uses `api.twilio.com`.
+    pub fn region(&mut self, region: String) -> &mut Self {
+        self.region = Some(region);
+        self
+    }
+
+    /// This function sets how long the `Client` waits before retrying a failed
+    /// send for the first time. This is 500 milliseconds by default. See
+    /// `max_retries` for more.
+    pub fn retry_base_delay(&mut self, retry_base_delay: Duration) -> &mut Self {
+        self.retry_base_delay = retry_base_delay;
+        self
+    }
+
+    /// This function sets the random number generator the `Client` uses to
+    /// pick how long to wait before retrying a failed send. `retry_rng` must
+    /// return a number from 0 to 1, which is the part of the longest possible
+    /// wait that's waited. This is useful for making retries predictable in
+    /// tests; a `retry_rng` that always returns 1 turns the randomness off.
+    /// See `max_retries` for more.
+    pub fn retry_rng(&mut self, retry_rng: impl Fn() -> f64 + Send + Sync + 'static) -> &mut Self {
+        self.retry_rng = Hook(Arc::new(retry_rng));
+        self
+    }
+
+    /// This function sets how long the `Client` waits for Twilio when sending,
+    /// canceling, or deleting messages. If this isn't set, the timeout set with
+    /// `timeout` is used.
+    ///
+    /// The time is measured from when the request starts to when the response
+    /// has been read, so it doesn't include time spent waiting for a send
+    /// permit when `max_concurrent_sends` is set.
+    pub fn send_timeout(&mut self, send_timeout: Duration) -> &mut Self {
+        self.send_timeout = Some(send_timeout);
+        self
+    }
+
+    /// This function sets whether the `Client` disables Nagle's algorithm on
+    /// its connections to Twilio. This is on by default, so that small
+    /// requests are sent without delay.
+    pub fn tcp_nodelay(&mut self, tcp_nodelay: bool) -> &mut Self {
+        self.tcp_nodelay = tcp_nodelay;
+        self
+    }
+
+    /// This function sets how long the `Client` waits for Twilio to respond to
+    /// any request, unless a more specific timeout like `send_timeout` is set
+    /// for it. By default, there's no timeout.
+    ///
+    /// Requests that time out fail with a `SendError::Network` error whose
+    /// `SendError::category` is `"timeout"`.
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Mutex,
+    };
+
+    use chrono::DateTime;
+    use futures::future::{self, BoxFuture};
+
+    use super::*;
+    use crate::{
+        message::HttpMethod,
+        test_server::{Response, TestServer},
+    };
+
+    /// The body of Twilio's response to a request to create a message.
+    const MESSAGE_JSON: &str =
+        r#"{ "sid": "SM0123456789abcdef0123456789abcdef", "status": "queued" }"#;
+
+    /// The `RecordingFallback` struct is a `FallbackSender` that records how
+    /// many retries had been made each time it's used.
+    #[derive(Debug, Default)]
+    struct RecordingFallback {
+        retries: Arc<AtomicU32>,
+        sends: Arc<Mutex<Vec<u32>>>,
+    }
+
+    impl FallbackSender for RecordingFallback {
+        fn send<'a>(
+            &'a self,
+            _message: &'a Message<'a>,
+            _error: SendError,
+        ) -> BoxFuture<'a, Result<(), SendError>> {
+            let retries = self.retries.load(Ordering::SeqCst);
+            self.sends.lock().unwrap().push(retries);
+            Box::pin(future::ready(Ok(())))
+        }
+    }
+
+    /// This function returns a `ClientBuilder` for a client that sends its
+    /// requests to `base_url`, like the URL of a `TestServer`.
+    fn test_client(base_url: String) -> ClientBuilder {
+        let mut builder = Client::builder();
+        builder
+            .account_sid("AC123".into())
+            .auth_token("token".into())
+            .base_url(base_url)
+            .retry_base_delay(Duration::from_millis(1));
+        builder
+    }
+
+    #[test]
+    fn auth_kind_depends_on_auth_method() {
+        let client = Client::builder()
+            .account_sid("".into())
+            .auth_token("".into())
+            .build()
+            .unwrap();
+        assert_eq!(AuthKind::AuthToken, client.auth_kind());
+        let client = Client::builder()
+            .account_sid("".into())
+            .api_key("".into(), "".into())
+            .build()
+            .unwrap();
+        assert_eq!(AuthKind::ApiKey, client.auth_kind());
+    }
+
+    #[test]
+    fn auth_username_depends_on_auth_method() {
+        let client = Client::builder()
+            .account_sid("AC123".into())
+            .auth_token("token".into())
+            .build()
+            .unwrap();
+        assert_eq!("AC123", client.auth_username());
+        let client = Client::builder()
+            .account_sid("AC123".into())
+            .api_key("SK123".into(), "secret".into())
+            .build()
+            .unwrap();
+        assert_eq!("SK123", client.auth_username());
+    }
+
+    #[test]
+    fn base_url_is_used_for_requests() {
+        let client = Client::builder()
+            .account_sid("AC123".into())
+            .auth_token("".into())
+            .build()
+            .unwrap();
+        assert_eq!(
+            "https://api.twilio.com/2010-04-01/Accounts/AC123/Messages.json",
+            client.messages_url()
+        );
+        let client = client
+            .to_builder()
+            .base_url("http://localhost:8080/".into())
+            .build()
+            .unwrap();
+        assert_eq!(
+            "http://localhost:8080/Accounts/AC123/Messages.json",
+            client.messages_url()
+        );
+    }
+
+    #[test]
+    fn builder_requires_auth_method() {
+        let builder_result = Client::builder().account_sid("".into()).build();
+        assert_eq!(
+            Some(ClientBuilderError::NoAuthMethodSet),
+            builder_result.err()
+        );
+    }
+
+    #[test]
+    fn builder_requires_sid() {
+        let builder_result = Client::builder().auth_token("".into()).build();
+        assert_eq!(
+            Some(ClientBuilderError::NoAccountSidSet),
+            builder_result.err()
+        );
+    }
+
+    #[test]
+    fn category_buckets_errors() {
+        assert_eq!("rate_limited", twilio_error_from_body(429, "").category());
+        assert_eq!("client_error", twilio_error_from_body(400, "").category());
+        assert_eq!("server_error", twilio_error_from_body(503, "").category());
+        assert_eq!("twilio_api", twilio_error_from_body(304, "").category());
+        assert_eq!("client_error", SendError::NoContentSid.category());
+        assert_eq!("client_error", SendError::NotScheduled.category());
+        let json_error = serde_json::from_str::<u8>("").unwrap_err();
+        assert_eq!("internal", SendError::Json(json_error).category());
+    }
+
+    #[test]
+    fn content_variables_are_sent_as_json() {
+        let message = Message::builder()
+            .to("+12025550189")
+            .from("+12025550123")
+            .content_sid("HX123")
+            .content_variables([("name", "Bob")].into())
+            .build()
+            .unwrap();
+        let params = message_params(&message, None, None).unwrap();
+        assert!(params.contains(&("ContentVariables", r#"{"name":"Bob"}"#.into())));
+        let form = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(&params)
+            .finish();
+        assert!(form.contains("ContentVariables=%7B%22name%22%3A%22Bob%22%7D"));
+    }
+
+    #[test]
+    fn default_content_sid_fills_in_for_variables_only_message() {
+        let message = Message::builder()
+            .to("")
+            .from("")
+            .content_variables([("name", "Bob")].into())
+            .build()
+            .unwrap();
+        let params = message_params(&message, Some("HX123"), None).unwrap();
+        assert!(params.contains(&("ContentSid", "HX123".into())));
+        assert!(matches!(
+            message_params(&message, None, None),
+            Err(SendError::NoContentSid)
+        ));
+    }
+
+    #[test]
+    fn default_content_sid_ignored_when_message_has_content() {
+        let message = Message::builder()
+            .to("")
+            .from("")
+            .body("hi")
+            .build()
+            .unwrap();
+        let params = message_params(&message, Some("HX123"), None).unwrap();
+        assert!(!params.iter().any(|(key, _)| *key == "ContentSid"));
+    }
+
+    #[test]
+    fn default_country_code_prefixes_national_numbers() {
+        assert_eq!(
+            "+12025550123",
+            with_country_code("(202) 555-0123", Some("+1"))
+        );
+        assert_eq!(
+            "+442071838750",
+            with_country_code("020 7183 8750", Some("+44"))
+        );
+        assert_eq!(
+            "whatsapp:+12025550123",
+            with_country_code("whatsapp:2025550123", Some("+1"))
+        );
+        assert_eq!(
+            "+442071838750",
+            with_country_code("+442071838750", Some("+1"))
+        );
+        assert_eq!("(202) 555-0123", with_country_code("(202) 555-0123", None));
+        let client = Client::builder()
+            .account_sid("".into())
+            .auth_token("".into())
+            .default_country_code("1")
+            .build()
+            .unwrap();
+        assert_eq!(Some("+1"), client.default_country_code.as_deref());
+    }
+
+    #[test]
+    fn delivery_option_params_are_sent() {
+        let message = Message::builder()
+            .to("")
+            .from("")
+            .body("hi")
+            .max_price(0.05)
+            .shorten_urls(false)
+            .smart_encoding(true)
+            .validity_period(600)
+            .build()
+            .unwrap();
+        let params = message_params(&message, None, None).unwrap();
+        assert!(params.contains(&("MaxPrice", "0.05".into())));
+        assert!(params.contains(&("ShortenUrls", "false".into())));
+        assert!(params.contains(&("SmartEncoded", "true".into())));
+        assert!(params.contains(&("ValidityPeriod", "600".into())));
+        assert_eq!(params.len(), params.capacity());
+    }
+
+    #[test]
+    fn error_helpers_inspect_errors() {
+        let error = twilio_error_from_body(400, r#"{"code": 21211}"#);
+        assert_eq!(Some(400), error.status());
+        assert_eq!(Some(21211), error.twilio_code());
+        assert!(!error.is_network());
+        assert_eq!(None, SendError::NoContentSid.status());
+        assert_eq!(None, SendError::NoContentSid.twilio_code());
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzip_form_compresses_params() {
+        use std::io::Read;
+
+        let params = [
+            ("To", Cow::Borrowed("+12025550189")),
+            ("Body", "hi & bye".into()),
+        ];
+        let compressed = gzip_form(&params).unwrap();
+        let mut form = String::new();
+        flate2::read::GzDecoder::new(compressed.as_slice())
+            .read_to_string(&mut form)
+            .unwrap();
+        assert_eq!("To=%2B12025550189&Body=hi+%26+bye", form);
+    }
+
+    #[test]
+    fn is_transient_only_matches_retryable_errors() {
+        assert!(is_transient(&twilio_error_from_body(429, "")));
+        assert!(is_transient(&twilio_error_from_body(503, "")));
+        assert!(!is_transient(&twilio_error_from_body(400, "")));
+        assert!(!is_transient(&twilio_error_from_body(501, "")));
+        assert!(!is_transient(&SendError::NoContentSid));
+        tokio_test::block_on(async {
+            let http = reqwest::Client::new();
+            let connect_error = http
+                .get(TestServer::unreachable_url().await)
+                .send()
+                .await
+                .unwrap_err();
+            assert!(is_transient(&SendError::Network(connect_error)));
+            let url_error = http.get("not a url").send().await.unwrap_err();
+            assert!(!is_transient(&SendError::Network(url_error)));
+        });
+    }
+
+    #[test]
+    fn lookup_url_requests_fields() {
+        let client = Client::builder()
+            .account_sid("".into())
+            .auth_token("".into())
+            .build()
+            .unwrap();
+        assert_eq!(
+            "https://lookups.twilio.com/v2/PhoneNumbers/%2B12025550189",
+            client.lookup_url("+12025550189", &[])
+        );
+        assert_eq!(
+            "https://lookups.twilio.com/v2/PhoneNumbers/%28202%29%20555-0189",
+            client.lookup_url("(202) 555-0189", &[])
+        );
+        assert_eq!(
+            "https://lookups.twilio.com/v2/PhoneNumbers/%2B12025550189?Fields=line_type_intelligence,caller_name",
+            client.lookup_url(
+                "+12025550189",
+                &[LookupField::LineTypeIntelligence, LookupField::CallerName]
+            )
+        );
+    }
+
+    #[test]
+    fn max_segments_per_message_refuses_long_bodies() {
+        let client = Client::builder()
+            .account_sid("".into())
+            .auth_token("".into())
+            .max_segments_per_message(1)
+            .build()
+            .unwrap();
+        let body = "a".repeat(161);
+        let message = Message::builder()
+            .to("")
+            .from("")
+            .body(&body)
+            .build()
+            .unwrap();
+        assert!(matches!(
+            client.check_segments(message.body.as_deref()),
+            Err(SendError::TooManySegments {
+                max: 1,
+                segments: 2
+            })
+        ));
+        let message = Message::builder()
+            .to("")
+            .from("")
+            .body("hi")
+            .build()
+            .unwrap();
+        assert!(client.check_segments(message.body.as_deref()).is_ok());
+    }
+
+    #[test]
+    fn max_segments_per_message_applies_to_every_send() {
+        tokio_test::block_on(async {
+            // nothing should reach twilio, so this fails if anything tries
+            let client = test_client(TestServer::unreachable_url().await)
+                .max_retries(0)
+                .max_segments_per_message(1)
+                .build()
+                .unwrap();
+            let body = "a".repeat(161);
+            let message = Message::builder()
+                .to("+12025550189")
+                .from("+12025550123")
+                .body(&body)
+                .build()
+                .unwrap();
+            let results = client.send_split(&message, 200, false, 1).await;
+            assert_eq!(1, results.len());
+            assert!(matches!(
+                results[0],
+                Err(SendError::TooManySegments {
+                    max: 1,
+                    segments: 2
+                })
+            ));
+            let results = client
+                .send_same_to_many(
+                    Content::Body(&body),
+                    "+12025550123",
+                    &["+12025550189", "+12025550190"],
+                    1,
+                )
+                .await;
+            assert_eq!(2, results.len());
+            assert!(results
+                .iter()
+                .all(|result| matches!(result, Err(SendError::TooManySegments { .. }))));
+        });
+    }
+
+    #[test]
+    fn message_url_includes_sid() {
+        let client = Client::builder()
+            .account_sid("AC123".into())
+            .auth_token("".into())
+            .build()
+            .unwrap();
+        assert_eq!(
+            "https://api.twilio.com/2010-04-01/Accounts/AC123/Messages/SM123.json",
+            client.message_url("SM123")
+        );
+    }
+
+    #[test]
+    fn region_and_edge_change_host() {
+        let client = Client::builder()
+            .account_sid("AC123".into())
+            .auth_token("".into())
+            .region("au1".into())
+            .edge("sydney".into())
+            .build()
+            .unwrap();
+        assert_eq!(
+            "https://api.sydney.au1.twilio.com/2010-04-01/Accounts/AC123/Messages.json",
+            client.messages_url()
+        );
+        let client = client.to_builder().region("ie1".into()).build().unwrap();
+        assert_eq!(
+            "https://api.sydney.ie1.twilio.com/2010-04-01",
+            client.base_url
+        );
+        assert_eq!(
+            "https://api.dublin.us1.twilio.com/2010-04-01",
+            regional_url("api", &Some("dublin".into()), &None)
+        );
+        assert_eq!(
+            "https://content.twilio.com/v1",
+            regional_url("content", &None, &None)
+        );
+    }
+
+    #[test]
+    fn resolve_uri_uses_base_url_host() {
+        let client = Client::builder()
+            .account_sid("AC123".into())
+            .auth_token("".into())
+            .build()
+            .unwrap();
+        assert_eq!(
+            "https://api.twilio.com/2010-04-01/Accounts/AC123/Messages.json?Page=1",
+            client.resolve_uri("/2010-04-01/Accounts/AC123/Messages.json?Page=1")
+        );
+        let client = client
+            .to_builder()
+            .base_url("http://localhost:8080".into())
+            .build()
+            .unwrap();
+        assert_eq!(
+            "http://localhost:8080/2010-04-01/Accounts/AC123/Messages.json",
+            client.resolve_uri("/2010-04-01/Accounts/AC123/Messages.json")
+        );
+    }
+
+    #[test]
+    fn preview_message_describes_request() {
+        let client = Client::builder()
+            .account_sid("AC123".into())
+            .api_key("SK123".into(), "secret".into())
+            .build()
+            .unwrap();
+        let message = Message::builder()
+            .to("+12025550189")
+            .from("+12025550123")
+            .body("hi")
+            .header("X-Request-Id", "42")
+            .build()
+            .unwrap();
+        assert_eq!(
+            PreparedRequest {
+                auth_username: "SK123".into(),
+                headers: vec![("X-Request-Id".into(), "42".into())],
+                params: vec![
+                    ("To".into(), "+12025550189".into()),
+                    ("From".into(), "+12025550123".into()),
+                    ("Body".into(), "hi".into()),
+                ],
+                url: "https://api.twilio.com/2010-04-01/Accounts/AC123/Messages.json".into(),
+            },
+            client.preview_message(&message).unwrap()
+        );
+    }
+
+    #[test]
+    fn prepared_request_converts_to_curl() {
+        let request = PreparedRequest {
+            auth_username: "SK123".into(),
+            headers: vec![("X-Request-Id".into(), "42".into())],
+            params: vec![
+                ("To".into(), "+12025550189".into()),
+                ("Body".into(), "it's & done".into()),
+            ],
+            url: "https://api.twilio.com/2010-04-01/Accounts/AC123/Messages.json".into(),
+        };
+        assert_eq!(
+            "curl -X POST 'https://api.twilio.com/2010-04-01/Accounts/AC123/Messages.json' \\
+  -u 'SK123:***' \\
+  -H 'X-Request-Id: 42' \\
+  --data-urlencode 'To=+12025550189' \\
+  --data-urlencode 'Body=it'\\''s & done'",
+            request.to_curl()
+        );
+        assert!(request
+            .to_curl_with_password("secret")
+            .contains("-u 'SK123:secret'"));
+    }
+
+    #[test]
+    fn redact_params_hides_sensitive_values() {
+        let params = [
+            ("To", Cow::Borrowed("+12025550189")),
+            ("Body", "the quick brown fox jumps".into()),
+            ("ContentVariables", r#"{"name":"Bob"}"#.into()),
+            ("ContentSid", "HX123".into()),
+        ];
+        let redacted = redact_params(&params);
+        assert_eq!(
+            vec![
+                ("To", "+*******0189".into()),
+                ("Body", "the quick brown …".into()),
+                ("ContentVariables", "<redacted>".into()),
+                ("ContentSid", "HX123".into()),
+            ],
+            redacted
+        );
+    }
+
+    #[test]
+    fn retry_after_parses_seconds() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(None, retry_after(&headers));
+        headers.insert(RETRY_AFTER, "3".parse().unwrap());
+        assert_eq!(Some(Duration::from_secs(3)), retry_after(&headers));
+        headers.insert(
+            RETRY_AFTER,
+            "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+        );
+        assert_eq!(None, retry_after(&headers));
+    }
+
+    #[test]
+    fn retry_delay_backs_off_with_jitter() {
+        let client = Client::builder()
+            .account_sid("".into())
+            .auth_token("".into())
+            .retry_base_delay(Duration::from_millis(100))
+            .retry_rng(|| 0.5)
+            .build()
+            .unwrap();
+        assert_eq!(Duration::from_millis(50), client.retry_delay(0, None));
+        assert_eq!(Duration::from_millis(100), client.retry_delay(1, None));
+        assert_eq!(Duration::from_millis(200), client.retry_delay(2, None));
+        // twilio knows best
+        assert_eq!(
+            Duration::from_secs(3),
+            client.retry_delay(2, Some(Duration::from_secs(3)))
+        );
+        let client = client.to_builder().retry_rng(|| 2.0).build().unwrap();
+        assert_eq!(Duration::from_millis(400), client.retry_delay(2, None));
+        let client = client
+            .to_builder()
+            .retry_base_delay(Duration::MAX)
+            .build()
+            .unwrap();
+        assert_eq!(Duration::MAX, client.retry_delay(1, None));
+        let jitter = random_fraction();
+        assert!((0.0..1.0).contains(&jitter));
+    }
+
+    #[test]
+    fn scheduled_at_params_are_sent() {
+        let message = Message::builder()
+            .to("")
+            .messaging_service_sid("MG123")
+            .body("hi")
+            .scheduled_at(DateTime::from_timestamp(4_102_444_800, 0).unwrap())
+            .build()
+            .unwrap();
+        let params = message_params(&message, None, None).unwrap();
+        assert!(params.contains(&("ScheduleType", "fixed".into())));
+        assert!(params.contains(&("SendAt", "2100-01-01T00:00:00Z".into())));
+    }
+
+    #[test]
+    fn send_scheduled_returns_cancelable_handle() {
+        tokio_test::block_on(async {
+            let server = TestServer::start(vec![
+                Response::new(201, message_json("scheduled")),
+                Response::new(200, message_json("canceled")),
+            ])
+            .await;
+            let client = test_client(server.url()).build().unwrap();
+            let scheduled_at = DateTime::from_timestamp(4_102_444_800, 0).unwrap();
+            let message = Message::builder()
+                .to("+12025550189")
+                .messaging_service_sid("MG123")
+                .body("hi")
+                .scheduled_at(scheduled_at)
+                .build()
+                .unwrap();
+            let scheduled = client.send_scheduled(&message).await.unwrap();
+            assert_eq!(
+                "SM0123456789abcdef0123456789abcdef",
+                scheduled.sid().as_str()
+            );
+            assert_eq!(scheduled_at, scheduled.scheduled_at());
+            let canceled = scheduled.cancel(&client).await.unwrap();
+            assert_eq!(MessageStatus::Canceled, canceled.status);
+            let requests = server.requests();
+            assert_eq!(
+                "/2010-04-01/Accounts/AC123/Messages/SM0123456789abcdef0123456789abcdef.json",
+                requests[1].path
+            );
+            assert_eq!("canceled", requests[1].form()["Status"]);
+            // unscheduled messages couldn't be canceled, so they aren't sent
+            let message = Message::builder()
+                .to("+12025550189")
+                .from("+12025550123")
+                .body("hi")
+                .build()
+                .unwrap();
+            assert!(matches!(
+                client.send_scheduled(&message).await,
+                Err(SendError::NotScheduled)
+            ));
+            assert_eq!(2, server.requests().len());
+        });
+    }
+
+    #[test]
+    fn send_many_keeps_order() {
+        let client = Client::builder()
+            .account_sid("".into())
+            .auth_token("".into())
+            .max_segments_per_message(1)
+            .build()
+            .unwrap();
+        // neither of these reach twilio, so we can see how they come back
+        let body = "a".repeat(161);
+        let messages = [
+            Message::builder()
+                .to("")
+                .from("")
+                .content_variables([("name", "Bob")].into())
+                .build()
+                .unwrap(),
+            Message::builder()
+                .to("")
+                .from("")
+                .body(&body)
+                .build()
+                .unwrap(),
+        ];
+        let results = tokio_test::block_on(client.send_many(&messages, 2));
+        assert!(matches!(results[0], Err(SendError::NoContentSid)));
+        assert!(matches!(results[1], Err(SendError::TooManySegments { .. })));
+    }
+
+    #[test]
+    fn send_message_falls_back_after_retries() {
+        tokio_test::block_on(async {
+            let fallback = RecordingFallback::default();
+            let retries = Arc::clone(&fallback.retries);
+            let sends = Arc::clone(&fallback.sends);
+            let client = test_client(TestServer::unreachable_url().await)
+                .max_retries(2)
+                .on_retry(move |_, _| {
+                    retries.fetch_add(1, Ordering::SeqCst);
+                })
+                .fallback(fallback)
+                .build()
+                .unwrap();
+            let message = Message::builder()
+                .to("")
+                .from("")
+                .body("hi")
+                .build()
+                .unwrap();
+            assert_eq!(
+                SendOutcome::Fallback,
+                client.send_message(&message).await.unwrap()
+            );
+            // the fallback is only used once twilio has been retried
+            assert_eq!(vec![2], *sends.lock().unwrap());
+        });
+    }
+
+    #[test]
+    fn send_message_skips_fallback_for_twilio_errors() {
+        tokio_test::block_on(async {
+            let server = TestServer::start(vec![Response::new(400, "")]).await;
+            let fallback = RecordingFallback::default();
+            let sends = Arc::clone(&fallback.sends);
+            let client = test_client(server.url())
+                .fallback(fallback)
+                .build()
+                .unwrap();
+            let message = Message::builder()
+                .to("")
+                .from("")
+                .body("hi")
+                .build()
+                .unwrap();
+            let error = client.send_message(&message).await.unwrap_err();
+            assert_eq!(Some(400), error.status());
+            assert!(sends.lock().unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn send_message_gives_up_after_max_retries() {
+        tokio_test::block_on(async {
+            let server = TestServer::start(vec![
+                Response::new(503, ""),
+                Response::new(500, ""),
+                Response::new(502, ""),
+            ])
+            .await;
+            let retries = Arc::new(Mutex::new(Vec::new()));
+            let on_retry_retries = Arc::clone(&retries);
+            let client = test_client(server.url())
+                .max_retries(2)
+                .on_retry(move |attempt, error| {
+                    on_retry_retries
+                        .lock()
+                        .unwrap()
+                        .push((attempt, error.status()));
+                })
+                .build()
+                .unwrap();
+            let message = Message::builder()
+                .to("")
+                .from("")
+                .body("hi")
+                .build()
+                .unwrap();
+            let error = client.send_message(&message).await.unwrap_err();
+            assert_eq!(Some(502), error.status());
+            assert_eq!(3, server.requests().len());
+            assert_eq!(
+                vec![(1, Some(503)), (2, Some(500))],
+                *retries.lock().unwrap()
+            );
+        });
+    }
+
+    #[test]
+    fn send_message_posts_form_to_twilio() {
+        tokio_test::block_on(async {
+            let server = TestServer::start(vec![Response::new(201, MESSAGE_JSON)]).await;
+            let client = test_client(server.url()).build().unwrap();
+            let message = Message::builder()
+                .to("+12025550189")
+                .from("+12025550123")
+                .body("hi & bye")
+                .build()
+                .unwrap();
+            let outcome = client.send_message(&message).await.unwrap();
+            let message_response = outcome.into_twilio_response().unwrap();
+            assert_eq!(MessageStatus::Queued, message_response.status);
+            let requests = server.requests();
+            assert_eq!(1, requests.len());
+            assert_eq!("POST", requests[0].method);
+            assert_eq!("/2010-04-01/Accounts/AC123/Messages.json", requests[0].path);
+            assert_eq!(
+                Some("Basic QUMxMjM6dG9rZW4="),
+                requests[0].header("Authorization")
+            );
+            let form = requests[0].form();
+            assert_eq!("+12025550189", form["To"]);
+            assert_eq!("+12025550123", form["From"]);
+            assert_eq!("hi & bye", form["Body"]);
+        });
+    }
+
+    #[test]
+    fn send_same_to_many_uses_client_defaults() {
+        tokio_test::block_on(async {
+            let server = TestServer::start(vec![
+                Response::new(201, MESSAGE_JSON),
+                Response::new(201, MESSAGE_JSON),
+            ])
+            .await;
+            let client = test_client(server.url())
+                .default_country_code("+1")
+                .build()
+                .unwrap();
+            let results = client
+                .send_same_to_many(
+                    Content::Template {
+                        content_sid: "HX123",
+                        content_variables: Some([("name", "Bob")].into()),
+                    },
+                    "+12025550123",
+                    &["(202) 555-0189", "+442079460018"],
+                    1,
+                )
+                .await;
+            assert!(results.iter().all(Result::is_ok));
+            let forms: Vec<_> = server
+                .requests()
+                .iter()
+                .map(|request| request.form())
+                .collect();
+            assert_eq!(2, forms.len());
+            assert_eq!("+12025550189", forms[0]["To"]);
+            assert_eq!("+442079460018", forms[1]["To"]);
+            for form in &forms {
+                assert_eq!("+12025550123", form["From"]);
+                assert_eq!("HX123", form["ContentSid"]);
+                assert_eq!(r#"{"name":"Bob"}"#, form["ContentVariables"]);
+                assert!(!form.contains_key("Body"));
+            }
+        });
+    }
+
+    #[test]
+    fn cancel_message_waits_for_send_permit() {
+        tokio_test::block_on(async {
+            let server = TestServer::start(vec![Response::new(200, MESSAGE_JSON)]).await;
+            let client = test_client(server.url())
+                .max_concurrent_sends(1)
+                .build()
+                .unwrap();
+            // while a send holds the only permit, canceling has to wait
+            let permit = client.send_permit().await;
+            let cancel = client.cancel_message("SM123");
+            assert!(tokio::time::timeout(Duration::from_millis(50), cancel)
+                .await
+                .is_err());
+            drop(permit);
+            client.cancel_message("SM123").await.unwrap();
+            let requests = server.requests();
+            assert_eq!(1, requests.len());
+            assert_eq!("POST", requests[0].method);
+            assert_eq!(
+                "/2010-04-01/Accounts/AC123/Messages/SM123.json",
+                requests[0].path
+            );
+            assert_eq!(
+                Some("Basic QUMxMjM6dG9rZW4="),
+                requests[0].header("Authorization")
+            );
+            assert_eq!("canceled", requests[0].form()["Status"]);
+        });
+    }
+
+    #[test]
+    fn redacted_audit_records_hide_numbers_and_body() {
+        tokio_test::block_on(async {
+            let response_json = r#"{
+                "sid": "SM0123456789abcdef0123456789abcdef",
+                "status": "queued",
+                "to": "+12025550189",
+                "from": "+12025550123",
+                "body": "your code is 314159"
+            }"#;
+            let error_json = r#"{
+                "code": 21211,
+                "message": "The 'To' number +12025550189 is not a valid phone number.",
+                "status": 400
+            }"#;
+            let server = TestServer::start(vec![
+                Response::new(201, response_json),
+                Response::new(400, error_json),
+            ])
+            .await;
+            let client = test_client(server.url())
+                .redact_audit_records(true)
+                .build()
+                .unwrap();
+            let message = Message::builder()
+                .to("+12025550189")
+                .from("+12025550123")
+                .body("your code is 314159")
+                .build()
+                .unwrap();
+            for status in [201, 400] {
+                let record = client.send_message_audited(&message).await.unwrap();
+                assert_eq!(status, record.response_status);
+                let record = format!("{record:?}");
+                assert!(!record.contains("2025550189"), "{record}");
+                assert!(!record.contains("2025550123"), "{record}");
+                assert!(!record.contains("your code"), "{record}");
+                assert!(!record.contains("314159"), "{record}");
+            }
+        });
+    }
+
+    #[test]
+    fn redirects_are_not_followed_by_default() {
+        tokio_test::block_on(async {
+            let server =
+                TestServer::start(vec![Response::new(302, "").header("Location", "/elsewhere")])
+                    .await;
+            let client = test_client(server.url()).build().unwrap();
+            let message = Message::builder()
+                .to("+12025550189")
+                .from("+12025550123")
+                .body("hi")
+                .build()
+                .unwrap();
+            let error = client.send_message(&message).await.unwrap_err();
+            assert_eq!(Some(302), error.status());
+            assert_eq!(1, server.requests().len());
+        });
+    }
+
+    #[test]
+    fn redirect_policy_can_follow_redirects() {
+        tokio_test::block_on(async {
+            let server = TestServer::start(vec![
+                Response::new(302, "").header("Location", "/elsewhere"),
+                Response::new(201, MESSAGE_JSON),
+            ])
+            .await;
+            let client = test_client(server.url())
+                .redirect_policy(Policy::limited(10))
+                .build()
+                .unwrap();
+            let message = Message::builder()
+                .to("+12025550189")
+                .from("+12025550123")
+                .body("hi")
+                .build()
+                .unwrap();
+            assert!(client.send_message(&message).await.is_ok());
+            let requests = server.requests();
+            assert_eq!(2, requests.len());
+            assert_eq!("/elsewhere", requests[1].path);
+        });
+    }
+
+    #[test]
+    fn from_pool_rotates_on_retry() {
+        tokio_test::block_on(async {
+            let server = TestServer::start(vec![
+                Response::new(503, ""),
+                Response::new(201, MESSAGE_JSON),
+                Response::new(201, MESSAGE_JSON),
+            ])
+            .await;
+            let client = test_client(server.url())
+                .max_retries(1)
+                .from_pool(vec!["+12025550123".into(), "+12025550124".into()])
+                .build()
+                .unwrap();
+            let message = Message::builder()
+                .to("+12025550189")
+                .messaging_service_sid("MG123")
+                .body("hi")
+                .build()
+                .unwrap();
+            assert!(client.send_message(&message).await.is_ok());
+            // a message with its own sender keeps it
+            let message = Message::builder()
+                .to("+12025550189")
+                .from("+12025550199")
+                .body("hi")
+                .build()
+                .unwrap();
+            assert!(client.send_message(&message).await.is_ok());
+            let forms: Vec<_> = server
+                .requests()
+                .iter()
+                .map(|request| request.form())
+                .collect();
+            assert_eq!("+12025550123", forms[0]["From"]);
+            assert_eq!("+12025550124", forms[1]["From"]);
+            assert_eq!("MG123", forms[1]["MessagingServiceSid"]);
+            assert_eq!("+12025550199", forms[2]["From"]);
+        });
+    }
+
+    #[test]
+    fn send_message_retries_until_success() {
+        tokio_test::block_on(async {
+            let server = TestServer::start(vec![
+                Response::new(503, ""),
+                Response::new(429, "").header("Retry-After", "0"),
+                Response::new(201, MESSAGE_JSON),
+            ])
+            .await;
+            let client = test_client(server.url()).max_retries(2).build().unwrap();
+            let message = Message::builder()
+                .to("")
+                .from("")
+                .body("hi")
+                .build()
+                .unwrap();
+            assert!(client.send_message(&message).await.is_ok());
+            assert_eq!(3, server.requests().len());
+        });
+    }
+
+    #[test]
+    fn send_message_returns_twilio_errors() {
+        tokio_test::block_on(async {
+            let body = r#"{
+                "code": 21211,
+                "message": "Invalid 'To' Phone Number",
+                "more_info": "https://www.twilio.com/docs/errors/21211",
+                "status": 400
+            }"#;
+            let server = TestServer::start(vec![Response::new(400, body)]).await;
+            // client errors aren't retried
+            let client = test_client(server.url()).max_retries(2).build().unwrap();
+            let message = Message::builder()
+                .to("")
+                .from("")
+                .body("hi")
+                .build()
+                .unwrap();
+            let error = client.send_message(&message).await.unwrap_err();
+            assert!(matches!(
+                error,
+                SendError::Twilio {
+                    code: Some(21211),
+                    status: 400,
+                    ..
+                }
+            ));
+            assert_eq!(1, server.requests().len());
+        });
+    }
+
+    #[test]
+    fn status_callback_params_are_sent() {
+        let message = Message::builder()
+            .to("")
+            .from("")
+            .body("")
+            .status_callback("https://example.com/status")
+            .status_callback_method(HttpMethod::Get)
+            .build()
+            .unwrap();
+        let params = message_params(&message, None, None).unwrap();
+        assert!(params.contains(&("StatusCallback", "https://example.com/status".into())));
+        assert!(params.contains(&("StatusCallbackMethod", "GET".into())));
+    }
+
+    #[test]
+    fn from_env_prefers_api_key() {
+        // this is the only test that touches these variables, so it's safe to
+        // set them while other tests run
+        env::remove_var("TWILIO_ACCOUNT_SID");
+        assert_eq!(
+            Some(ClientBuilderError::MissingEnvVar("TWILIO_ACCOUNT_SID")),
+            Client::from_env().err()
+        );
+        env::set_var("TWILIO_ACCOUNT_SID", "AC123");
+        env::set_var("TWILIO_AUTH_TOKEN", "token");
+        env::remove_var("TWILIO_API_KEY");
+        assert_eq!(AuthKind::AuthToken, Client::from_env().unwrap().auth_kind());
+        env::set_var("TWILIO_API_KEY", "SK123");
+        env::remove_var("TWILIO_API_SECRET");
+        assert_eq!(
+            Some(ClientBuilderError::MissingEnvVar("TWILIO_API_SECRET")),
+            Client::from_env().err()
+        );
+        env::set_var("TWILIO_API_SECRET", "secret");
+        assert_eq!(AuthKind::ApiKey, Client::from_env().unwrap().auth_kind());
+    }
+
+    #[test]
+    fn test_credentials_use_auth_token() {
+        let client = Client::test_credentials("AC123".into(), "token".into()).unwrap();
+        assert_eq!(AuthKind::AuthToken, client.auth_kind());
+        assert_eq!("AC123", client.auth_username());
+    }
+
+    #[test]
+    fn timeouts_are_set_per_operation() {
+        let client = Client::builder()
+            .account_sid("".into())
+            .auth_token("".into())
+            .timeout(Duration::from_secs(30))
+            .send_timeout(Duration::from_secs(5))
+            .build()
+            .unwrap();
+        assert_eq!(Some(Duration::from_secs(30)), client.timeout);
+        assert_eq!(Some(Duration::from_secs(5)), client.send_timeout);
+        assert_eq!(None, client.fetch_timeout);
+        let derived = client.to_builder().build().unwrap();
+        assert_eq!(Some(Duration::from_secs(5)), derived.send_timeout);
+    }
+
+    #[test]
+    fn to_builder_keeps_settings() {
+        let client = Client::builder()
+            .account_sid("AC123".into())
+            .api_key("SK123".into(), "secret".into())
+            .default_content_sid("HX123".into())
+            .build()
+            .unwrap();
+        let derived = client.to_builder().max_retries(5).build().unwrap();
+        assert_eq!("SK123", derived.auth_username());
+        assert_eq!(Some("HX123"), derived.default_content_sid.as_deref());
+        assert_eq!(5, derived.max_retries);
+        assert_eq!(0, client.max_retries);
+    }
+
+    #[test]
+    fn twilio_error_parses_details() {
+        let body = r#"{
+            "code": 21211,
+            "message": "Invalid 'To' Phone Number",
+            "more_info": "https://www.twilio.com/docs/errors/21211",
+            "status": 400
+        }"#;
+        match twilio_error_from_body(400, body) {
+            SendError::Twilio {
+                code,
+                message,
+                status,
+                ..
+            } => {
+                assert_eq!(Some(21211), code);
+                assert_eq!(Some("Invalid 'To' Phone Number"), message.as_deref());
+                assert_eq!(400, status);
+            }
+            error => panic!("unexpected error {error:?}"),
+        }
+        assert!(matches!(
+            twilio_error_from_body(502, "<html>Bad Gateway</html>"),
+            SendError::Twilio {
+                code: None,
+                status: 502,
+                ..
+            }
+        ));
+    }
+
+    /// This function returns the JSON Twilio responds with for a message with
+    /// `status`.
+    fn message_json(status: &str) -> String {
+        format!(r#"{{ "sid": "SM0123456789abcdef0123456789abcdef", "status": "{status}" }}"#)
+    }
+
+    #[test]
+    fn wait_for_delivery_returns_final_status() {
+        tokio_test::block_on(async {
+            let server = TestServer::start(vec![
+                Response::new(200, message_json("queued")),
+                Response::new(200, message_json("sent")),
+                Response::new(200, message_json("delivered")),
+            ])
+            .await;
+            let client = test_client(server.url()).build().unwrap();
+            let message_response = client
+                .wait_for_delivery("SM123", Duration::from_millis(1), Duration::from_secs(5))
+                .await
+                .unwrap();
+            assert_eq!(MessageStatus::Delivered, message_response.status);
+            let requests = server.requests();
+            assert_eq!(3, requests.len());
+            assert!(requests.iter().all(|request| request.method == "GET"
+                && request.path == "/2010-04-01/Accounts/AC123/Messages/SM123.json"));
+        });
+    }
+
+    #[test]
+    fn wait_for_delivery_times_out() {
+        tokio_test::block_on(async {
+            let server =
+                TestServer::start(vec![Response::new(200, message_json("sent")); 10]).await;
+            let client = test_client(server.url()).build().unwrap();
+            let result = client
+                .wait_for_delivery(
+                    "SM123",
+                    Duration::from_millis(20),
+                    Duration::from_millis(50),
+                )
+                .await;
+            assert!(matches!(
+                result,
+                Err(SendError::DeliveryTimeout {
+                    status: MessageStatus::Sent
+                })
+            ));
+            // it gives up once another poll would go past the timeout
+            assert!(server.requests().len() <= 3);
+        });
+    }
+
+    #[test]
+    fn fetch_price_waits_for_billing() {
+        tokio_test::block_on(async {
+            let billed = r#"{
+                "sid": "SM0123456789abcdef0123456789abcdef",
+                "status": "delivered",
+                "price": "-0.00790",
+                "price_unit": "USD"
+            }"#;
+            let server = TestServer::start(vec![
+                Response::new(200, message_json("sent")),
+                Response::new(200, message_json("delivered")),
+                Response::new(200, billed),
+            ])
+            .await;
+            let client = test_client(server.url()).build().unwrap();
+            let price = client
+                .fetch_price("SM123", Duration::from_millis(1), Duration::from_secs(5))
+                .await
+                .unwrap();
+            assert_eq!(Some(-0.0079), price);
+            assert_eq!(3, server.requests().len());
+        });
+    }
+
+    #[test]
+    fn fetch_price_times_out_without_price() {
+        tokio_test::block_on(async {
+            let server =
+                TestServer::start(vec![Response::new(200, message_json("sent")); 10]).await;
+            let client = test_client(server.url()).build().unwrap();
+            let price = client
+                .fetch_price(
+                    "SM123",
+                    Duration::from_millis(20),
+                    Duration::from_millis(50),
+                )
+                .await
+                .unwrap();
+            assert_eq!(None, price);
+        });
+    }
+
+    #[test]
+    fn fetch_messages_keeps_order() {
+        tokio_test::block_on(async {
+            let server = TestServer::start(vec![
+                Response::new(200, message_json("delivered")),
+                Response::new(404, r#"{ "code": 20404, "status": 404 }"#),
+                Response::new(200, message_json("failed")),
+            ])
+            .await;
+            let client = test_client(server.url()).build().unwrap();
+            let results = client.fetch_messages(&["SM1", "SM2", "SM3"], 1).await;
+            assert_eq!(3, results.len());
+            assert_eq!(
+                MessageStatus::Delivered,
+                results[0].as_ref().unwrap().status
+            );
+            assert_eq!(Some(404), results[1].as_ref().unwrap_err().status());
+            assert_eq!(MessageStatus::Failed, results[2].as_ref().unwrap().status);
+            let paths: Vec<_> = server
+                .requests()
+                .into_iter()
+                .map(|request| request.path)
+                .collect();
+            assert_eq!(
+                vec![
+                    "/2010-04-01/Accounts/AC123/Messages/SM1.json",
+                    "/2010-04-01/Accounts/AC123/Messages/SM2.json",
+                    "/2010-04-01/Accounts/AC123/Messages/SM3.json",
+                ],
+                paths
+            );
+        });
+    }
+
+    #[test]
+    fn watch_message_yields_each_new_status() {
+        tokio_test::block_on(async {
+            let server = TestServer::start(vec![
+                Response::new(200, message_json("queued")),
+                Response::new(200, message_json("queued")),
+                Response::new(200, message_json("sent")),
+                Response::new(200, message_json("delivered")),
+            ])
+            .await;
+            let client = test_client(server.url()).build().unwrap();
+            let statuses: Vec<_> = client
+                .watch_message("SM123", Duration::from_millis(1))
+                .map(Result::unwrap)
+                .collect()
+                .await;
+            assert_eq!(
+                vec![
+                    MessageStatus::Queued,
+                    MessageStatus::Sent,
+                    MessageStatus::Delivered
+                ],
+                statuses
+            );
+            assert_eq!(4, server.requests().len());
+        });
+    }
+
+    #[test]
+    fn whoami_only_tolerates_refusals() {
+        let account = r#"{
+            "date_created": "Thu, 30 Jul 2015 20:00:00 +0000",
+            "friendly_name": "Production",
+            "sid": "AC0123456789abcdef0123456789abcdef",
+            "status": "active",
+            "type": "Full"
+        }"#;
+        tokio_test::block_on(async {
+            let server =
+                TestServer::start(vec![Response::new(200, account), Response::new(403, "")]).await;
+            let client = test_client(server.url())
+                .api_key("SK123".into(), "secret".into())
+                .build()
+                .unwrap();
+            let identity = client.whoami().await.unwrap();
+            assert_eq!("Production", identity.friendly_name);
+            assert_eq!(None, identity.api_key_friendly_name);
+            assert_eq!(
+                "/2010-04-01/Accounts/AC123/Keys/SK123.json",
+                server.requests()[1].path
+            );
+            let server =
+                TestServer::start(vec![Response::new(200, account), Response::new(500, "")]).await;
+            let client = test_client(server.url())
+                .api_key("SK123".into(), "secret".into())
+                .build()
+                .unwrap();
+            assert_eq!(Some(500), client.whoami().await.unwrap_err().status());
+        });
     }
 
     #[test]
@@ -0,0 +1,136 @@
+//! This module provides an interface for interacting with Twilio Content
+//! templates.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::sid::{self, Sid};
+
+/// The `ContentTemplate` struct represents a Twilio Content template, as
+/// returned by `Client::fetch_content`.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct ContentTemplate {
+    /// The human-readable name of the template.
+    pub friendly_name: String,
+    /// The language of the template, like `en`.
+    pub language: String,
+    /// The SID of the template.
+    pub sid: Sid<sid::Content>,
+    /// The versions of the template for each content type Twilio supports,
+    /// keyed by the name of the type, like `twilio/text`.
+    pub types: HashMap<String, ContentType>,
+    /// The default values of the template's variables, keyed by the name of
+    /// the variable.
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+}
+
+/// The `ContentType` struct represents the version of a Twilio Content
+/// template for a single content type. Only the body is kept, as the rest
+/// differs between content types.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct ContentType {
+    /// The body of the template for this content type, if it has one.
+    pub body: Option<String>,
+}
+
+impl ContentTemplate {
+    /// This function returns the body of the template. The body of the
+    /// `twilio/text` type is preferred, as it's what most recipients see.
+    pub fn body(&self) -> Option<&str> {
+        if let Some(body) = self
+            .types
+            .get("twilio/text")
+            .and_then(|text| text.body.as_deref())
+        {
+            return Some(body);
+        }
+        // otherwise, pick any body. let's sort so that the same one is picked
+        // every time
+        let mut type_names: Vec<_> = self.types.keys().collect();
+        type_names.sort();
+        type_names
+            .into_iter()
+            .find_map(|type_name| self.types[type_name].body.as_deref())
+    }
+
+    /// This function renders the body of the template locally, replacing each
+    /// `{{variable}}` with its value in `content_variables`, or the template's
+    /// default value if it isn't there. Variables without either are left as
+    /// they are, so they're easy to spot. A template without a body renders as
+    /// an empty string.
+    ///
+    /// This is only a preview: Twilio renders the template itself when the
+    /// message is sent.
+    pub fn render(&self, content_variables: &HashMap<&str, &str>) -> String {
+        let mut body = match self.body() {
+            Some(body) => body,
+            None => return String::new(),
+        };
+        let mut rendered = String::with_capacity(body.len());
+        while let Some(start) = body.find("{{") {
+            let end = match body[start + 2..].find("}}") {
+                Some(end) => start + 2 + end,
+                None => break,
+            };
+            rendered.push_str(&body[..start]);
+            let name = body[start + 2..end].trim();
+            let value = content_variables
+                .get(name)
+                .copied()
+                .or_else(|| self.variables.get(name).map(String::as_str));
+            match value {
+                Some(value) => rendered.push_str(value),
+                None => rendered.push_str(&body[start..end + 2]),
+            }
+            body = &body[end + 2..];
+        }
+        rendered.push_str(body);
+        rendered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template() -> ContentTemplate {
+        let json = r#"{
+            "friendly_name": "order_update",
+            "language": "en",
+            "sid": "HX0123456789abcdef0123456789abcdef",
+            "types": {
+                "twilio/media": { "body": "Media {{1}}", "media": ["https://example.com/a.png"] },
+                "twilio/text": { "body": "Hi {{1}}, your order {{ 2 }} is {{3}}." }
+            },
+            "variables": { "1": "there" }
+        }"#;
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn body_prefers_text_type() {
+        assert_eq!(
+            Some("Hi {{1}}, your order {{ 2 }} is {{3}}."),
+            template().body()
+        );
+    }
+
+    #[test]
+    fn render_substitutes_variables() {
+        let content_variables = HashMap::from([("2", "#42"), ("3", "shipped")]);
+        assert_eq!(
+            "Hi there, your order #42 is shipped.",
+            template().render(&content_variables)
+        );
+    }
+
+    #[test]
+    fn render_leaves_missing_variables() {
+        assert_eq!(
+            "Hi there, your order {{ 2 }} is {{3}}.",
+            template().render(&HashMap::new())
+        );
+    }
+}
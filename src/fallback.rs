@@ -0,0 +1,77 @@
+//! This module provides an interface for sending messages some other way when
+//! Twilio can't be reached.
+
+use std::fmt::Debug;
+
+use futures::future::{self, BoxFuture};
+
+use crate::{client::SendError, Message};
+
+/// The `FallbackSender` trait is implemented by ways of delivering a message
+/// when Twilio can't be reached, like carrier email-to-SMS gateways. Set one on
+/// a `Client` with `ClientBuilder::fallback`.
+///
+/// # Implementing
+///
+/// ```rust
+/// use fullsend::{client::SendError, fallback::FallbackSender, Message};
+/// use futures::future::BoxFuture;
+///
+/// #[derive(Debug)]
+/// struct EmailGateway;
+///
+/// impl FallbackSender for EmailGateway {
+///     fn send<'a>(
+///         &'a self,
+///         message: &'a Message<'a>,
+///         error: SendError,
+///     ) -> BoxFuture<'a, Result<(), SendError>> {
+///         Box::pin(async move {
+///             // email the message to the recipient's gateway here
+///             Ok(())
+///         })
+///     }
+/// }
+/// ```
+pub trait FallbackSender: Debug + Send + Sync {
+    /// This function sends `message`, which couldn't be sent through Twilio
+    /// because of `error`. Return `error` if the message can't be sent this way
+    /// either.
+    fn send<'a>(
+        &'a self,
+        message: &'a Message<'a>,
+        error: SendError,
+    ) -> BoxFuture<'a, Result<(), SendError>>;
+}
+
+/// The `NoFallback` struct is a `FallbackSender` that doesn't send anything,
+/// and returns the original error.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoFallback;
+
+impl FallbackSender for NoFallback {
+    fn send<'a>(
+        &'a self,
+        _message: &'a Message<'a>,
+        error: SendError,
+    ) -> BoxFuture<'a, Result<(), SendError>> {
+        Box::pin(future::ready(Err(error)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_fallback_returns_error() {
+        let message = Message::builder()
+            .to("")
+            .from("")
+            .body("hi")
+            .build()
+            .unwrap();
+        let result = tokio_test::block_on(NoFallback.send(&message, SendError::NoContentSid));
+        assert!(matches!(result, Err(SendError::NoContentSid)));
+    }
+}
@@ -26,9 +26,21 @@
 //! # })
 //! ```
 
+pub mod account;
 mod auth;
+#[cfg(feature = "blocking")]
+pub mod blocking;
 pub mod client;
+pub mod content;
+pub mod fallback;
+pub mod lookup;
 pub mod message;
+pub mod sid;
+pub mod test_numbers;
+#[cfg(test)]
+mod test_server;
+pub mod webhook;
 
+pub use auth::AuthKind;
 pub use client::Client;
-pub use message::Message;
+pub use message::{Message, OwnedMessage};
@@ -0,0 +1,116 @@
+//! This module provides an interface for looking up phone numbers with Twilio
+//! Lookup.
+
+use serde::Deserialize;
+
+/// The `LookupResult` struct represents what Twilio Lookup knows about a phone
+/// number, as returned by `Client::lookup`.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct LookupResult {
+    /// The caller name of the number, if it was requested with
+    /// `LookupField::CallerName`.
+    #[serde(default)]
+    pub caller_name: Option<CallerName>,
+    /// The country calling code of the number, like `1`.
+    pub calling_country_code: Option<String>,
+    /// The ISO 3166 code of the number's country, like `US`, if the number is
+    /// valid.
+    pub country_code: Option<String>,
+    /// The line type of the number, if it was requested with
+    /// `LookupField::LineTypeIntelligence`.
+    #[serde(default)]
+    pub line_type_intelligence: Option<LineTypeIntelligence>,
+    /// The number in its country's national format.
+    pub national_format: Option<String>,
+    /// The number in E.164 format, like `+12025550189`.
+    pub phone_number: String,
+    /// Whether the number is a valid phone number.
+    pub valid: bool,
+    /// Why the number isn't valid, like `TOO_SHORT`, if it isn't.
+    #[serde(default)]
+    pub validation_errors: Option<Vec<String>>,
+}
+
+/// The `CallerName` struct represents the caller name data of a phone number.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct CallerName {
+    /// The name registered to the number.
+    pub caller_name: Option<String>,
+    /// Whether the number is registered to a `BUSINESS` or a `CONSUMER`.
+    pub caller_type: Option<String>,
+    /// The Twilio error code, if the caller name couldn't be looked up.
+    pub error_code: Option<u32>,
+}
+
+/// The `LineTypeIntelligence` struct represents the line type data of a phone
+/// number.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct LineTypeIntelligence {
+    /// The name of the number's carrier.
+    pub carrier_name: Option<String>,
+    /// The Twilio error code, if the line type couldn't be looked up.
+    pub error_code: Option<u32>,
+    /// The line type of the number, like `mobile`, `landline`, or
+    /// `nonFixedVoip`.
+    #[serde(rename = "type")]
+    pub type_: Option<String>,
+}
+
+impl LineTypeIntelligence {
+    /// This function returns whether the number is likely able to receive
+    /// SMS: mobile numbers and non-fixed VoIP numbers usually can, while
+    /// landlines and the like usually can't.
+    pub fn is_sms_capable(&self) -> bool {
+        matches!(self.type_.as_deref(), Some("mobile" | "nonFixedVoip"))
+    }
+}
+
+/// The `LookupField` enum represents the optional data packages that can be
+/// requested with a lookup. Most of them are billed per lookup.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LookupField {
+    /// The name the number is registered to.
+    CallerName,
+    /// The line type and carrier of the number.
+    LineTypeIntelligence,
+}
+
+impl LookupField {
+    /// This function returns the name of the field as Twilio expects it.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LookupField::CallerName => "caller_name",
+            LookupField::LineTypeIntelligence => "line_type_intelligence",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_result_parses_line_type_intelligence() {
+        let json = r#"{
+            "calling_country_code": "1",
+            "country_code": "US",
+            "phone_number": "+12025550189",
+            "national_format": "(202) 555-0189",
+            "valid": true,
+            "validation_errors": [],
+            "caller_name": null,
+            "line_type_intelligence": {
+                "carrier_name": "Verizon Wireless",
+                "error_code": null,
+                "mobile_country_code": "310",
+                "mobile_network_code": "456",
+                "type": "mobile"
+            },
+            "url": "https://lookups.twilio.com/v2/PhoneNumbers/+12025550189"
+        }"#;
+        let result: LookupResult = serde_json::from_str(json).unwrap();
+        assert!(result.valid);
+        assert_eq!(Some("US"), result.country_code.as_deref());
+        assert!(result.line_type_intelligence.unwrap().is_sms_capable());
+    }
+}
@@ -1,6 +1,20 @@
 //! This module provides an interface for interacting with Twilio messages.
 
-use std::collections::HashMap;
+use std::{borrow::Cow, collections::HashMap, time::SystemTime};
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use serde::{
+    de::{self, Deserializer},
+    ser::SerializeStruct,
+    Deserialize, Serialize, Serializer,
+};
+
+use crate::{
+    client::{self, SendError},
+    sid::{self, Sid},
+    Client,
+};
 
 /// The `Message` struct is the interface for interacting with Twilio messages.
 ///
@@ -20,32 +34,1017 @@ use std::collections::HashMap;
 ///     .body(message)
 ///     .build();
 /// ```
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Message<'a> {
-    pub(crate) body: Option<&'a str>,
-    pub(crate) content_sid: Option<&'a str>,
-    pub(crate) content_variables: Option<HashMap<&'a str, &'a str>>,
-    pub(crate) from: Option<&'a str>,
-    pub(crate) media_urls: Option<Vec<&'a str>>,
-    pub(crate) messaging_service_sid: Option<&'a str>,
-    pub(crate) to: &'a str,
+    pub(crate) allow_body_with_content: bool,
+    pub(crate) body: Option<Cow<'a, str>>,
+    pub(crate) channel: Channel,
+    pub(crate) content_sid: Option<Cow<'a, str>>,
+    pub(crate) content_variables: Option<HashMap<Cow<'a, str>, Cow<'a, str>>>,
+    pub(crate) from: Option<Cow<'a, str>>,
+    pub(crate) headers: Vec<(Cow<'a, str>, Cow<'a, str>)>,
+    pub(crate) max_price: Option<f64>,
+    pub(crate) media_urls: Option<Vec<Cow<'a, str>>>,
+    pub(crate) messaging_service_sid: Option<Cow<'a, str>>,
+    pub(crate) require_e164: bool,
+    pub(crate) require_gsm7: bool,
+    pub(crate) scheduled_at: Option<DateTime<Utc>>,
+    pub(crate) shorten_urls: Option<bool>,
+    pub(crate) smart_encoding: Option<bool>,
+    pub(crate) status_callback: Option<Cow<'a, str>>,
+    pub(crate) status_callback_method: Option<HttpMethod>,
+    pub(crate) to: Cow<'a, str>,
+    pub(crate) validity_period: Option<u32>,
 }
 
+/// The `OwnedMessage` type is a `Message` that owns all of its data, as
+/// returned by `Message::into_owned`. It can be stored, or moved to another
+/// task, and sent like any other `Message`.
+pub type OwnedMessage = Message<'static>;
+
 impl<'a> Message<'a> {
     /// This function returns a `MessageBuilder` to use to create a `Message`.
     pub fn builder() -> MessageBuilder<'a> {
         MessageBuilder::default()
     }
+
+    /// This function returns a copy of the message that owns all of its data,
+    /// so that it can outlive whatever it was built from. This is useful for
+    /// storing messages or sending them from another task.
+    pub fn into_owned(self) -> OwnedMessage {
+        Message {
+            allow_body_with_content: self.allow_body_with_content,
+            body: self.body.map(owned),
+            channel: self.channel,
+            content_sid: self.content_sid.map(owned),
+            content_variables: self.content_variables.map(|content_variables| {
+                content_variables
+                    .into_iter()
+                    .map(|(name, value)| (owned(name), owned(value)))
+                    .collect()
+            }),
+            from: self.from.map(owned),
+            headers: self
+                .headers
+                .into_iter()
+                .map(|(name, value)| (owned(name), owned(value)))
+                .collect(),
+            max_price: self.max_price,
+            media_urls: self
+                .media_urls
+                .map(|media_urls| media_urls.into_iter().map(owned).collect()),
+            messaging_service_sid: self.messaging_service_sid.map(owned),
+            require_e164: self.require_e164,
+            require_gsm7: self.require_gsm7,
+            scheduled_at: self.scheduled_at,
+            shorten_urls: self.shorten_urls,
+            smart_encoding: self.smart_encoding,
+            status_callback: self.status_callback.map(owned),
+            status_callback_method: self.status_callback_method,
+            to: owned(self.to),
+            validity_period: self.validity_period,
+        }
+    }
+
+    /// This function checks the message against Twilio's limits and the
+    /// checks `MessageBuilder::build` runs, reporting every problem it finds
+    /// rather than just the first:
+    ///
+    /// - the body can be at most 1600 characters long
+    /// - there can be at most 10 media URLs, and they must be `http` or
+    ///   `https` URLs
+    /// - the content can't be contradictory, like a body with a Twilio
+    ///   Content SID
+    /// - `to` and `from` must be on the same channel, and media sent over
+    ///   WhatsApp needs a caption
+    /// - the phone numbers must be in E.164 format and the body must be GSM-7,
+    ///   if the builder required it
+    /// - scheduled messages must be sent through a Messaging Service, and
+    ///   can't be scheduled in the past
+    ///
+    /// Bodies longer than 1600 characters are allowed by `build`, so that they
+    /// can be sent with `Client::send_split`.
+    pub fn validate_limits(&self) -> Result<(), Vec<MessageBuilderError>> {
+        let mut errors = Vec::new();
+        if let Some(body_char_len) = self.body_char_len() {
+            if body_char_len > MAX_BODY_CHARS {
+                errors.push(MessageBuilderError::BodyTooLong(body_char_len));
+            }
+        }
+        errors.extend(self.check(SystemTime::now().into()));
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// This function runs the checks `MessageBuilder::build` runs on the
+    /// message, returning every problem it finds in the order `build` checks
+    /// for them. `now` is the current time.
+    fn check(&self, now: DateTime<Utc>) -> Vec<MessageBuilderError> {
+        let mut errors = Vec::new();
+        // validate that we have a sender: either a from or messaging service,
+        // or both
+        if self.from.is_none() && self.messaging_service_sid.is_none() {
+            errors.push(MessageBuilderError::NoSenderSet);
+        }
+        // validate that we have content: any of body, media URL, or Content SID.
+        // content variables alone are also fine, since the client may have a
+        // default content SID to use them with
+        if self.body.is_none()
+            && self.media_urls.is_none()
+            && self.content_sid.is_none()
+            && self.content_variables.is_none()
+        {
+            errors.push(MessageBuilderError::NoMessageSet);
+        }
+        // validate that the content isn't contradictory. content variables
+        // without a content SID are only fine if the client's default content
+        // SID will be used, which is only when there's no body or media
+        if self.body.is_some() && self.content_sid.is_some() && !self.allow_body_with_content {
+            errors.push(MessageBuilderError::BodyWithContentSid);
+        }
+        if self.content_variables.is_some()
+            && self.content_sid.is_none()
+            && (self.body.is_some() || self.media_urls.is_some())
+        {
+            errors.push(MessageBuilderError::ContentVariablesWithoutContentSid);
+        }
+        // validate that twilio will accept the media URLs
+        if let Some(media_urls) = &self.media_urls {
+            if media_urls.len() > MAX_MEDIA_URLS {
+                errors.push(MessageBuilderError::TooManyMediaUrls(media_urls.len()));
+            }
+            for media_url in media_urls.iter().filter(|url| !is_http_url(url)) {
+                errors.push(MessageBuilderError::InvalidMediaUrl(media_url.to_string()));
+            }
+        }
+        // validate that the addresses are on the same channel
+        if let Some(from) = self.from.as_deref() {
+            if from.starts_with(WHATSAPP_PREFIX) != self.to.starts_with(WHATSAPP_PREFIX) {
+                errors.push(MessageBuilderError::MixedChannelAddresses);
+            }
+        }
+        // validate that media has a caption on channels that require one
+        if self.channel == Channel::Whatsapp
+            && self.media_urls.is_some()
+            && self.body.is_none()
+            && self.content_sid.is_none()
+        {
+            errors.push(MessageBuilderError::MediaRequiresCaption);
+        }
+        // validate that none of the headers would clobber our credentials
+        for (name, _) in self
+            .headers
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case("Authorization"))
+        {
+            errors.push(MessageBuilderError::ReservedHeader(name.to_string()));
+        }
+        // twilio only schedules messages sent through a messaging service, and
+        // it can't send them in the past, give or take a little clock skew
+        if let Some(scheduled_at) = self.scheduled_at {
+            if self.messaging_service_sid.is_none() {
+                errors.push(MessageBuilderError::ScheduleRequiresMessagingService);
+            }
+            if scheduled_at + SCHEDULE_SKEW < now {
+                errors.push(MessageBuilderError::ScheduleInPast { now, scheduled_at });
+            }
+        }
+        // validate that the phone numbers are in E.164 format if that's
+        // required. alphanumeric sender IDs aren't phone numbers, so they're
+        // left alone
+        if self.require_e164 {
+            if !is_e164(&self.to) {
+                errors.push(MessageBuilderError::InvalidPhoneNumber(self.to.to_string()));
+            }
+            if let Some(from) = self.from.as_deref() {
+                let is_sender_id = !from.contains(':') && from.chars().any(char::is_alphabetic);
+                if !is_sender_id && !is_e164(from) {
+                    errors.push(MessageBuilderError::InvalidPhoneNumber(from.into()));
+                }
+            }
+        }
+        // validate that the body can be sent as GSM-7 if that's required
+        if self.require_gsm7 {
+            if let Some(first_offending_char) = self
+                .body
+                .as_deref()
+                .and_then(|body| body.chars().find(|c| !is_gsm7(*c)))
+            {
+                errors.push(MessageBuilderError::BodyNotGsm7 {
+                    first_offending_char,
+                });
+            }
+        }
+        errors
+    }
+
+    /// This function returns the length of the message's body in bytes, or
+    /// `None` if the message has no body.
+    pub fn body_byte_len(&self) -> Option<usize> {
+        self.body.as_deref().map(str::len)
+    }
+
+    /// This function returns the length of the message's body in Unicode
+    /// scalar values (`char`s), or `None` if the message has no body.
+    pub fn body_char_len(&self) -> Option<usize> {
+        self.body.as_deref().map(|body| body.chars().count())
+    }
+
+    /// This function returns the number of SMS segments the message's body
+    /// will be sent as, or `None` if the message has no body. Carriers bill
+    /// per segment.
+    ///
+    /// Bodies that can be encoded with GSM-7 fit 160 characters in a single
+    /// segment, and 153 per segment once they need more than one (characters
+    /// from the GSM-7 extension table count twice). Other bodies are sent as
+    /// UCS-2, which fits 70 UTF-16 code units in a single segment, and 67 per
+    /// segment otherwise.
+    pub fn body_segments(&self) -> Option<u32> {
+        self.body.as_deref().map(segments)
+    }
+}
+
+/// The `InboundMessage` struct represents a message received by one of your
+/// Twilio numbers, as delivered to your webhook.
+///
+/// # Creating
+///
+/// Parse the `application/x-www-form-urlencoded` body Twilio POSTs to your
+/// webhook:
+///
+/// ```rust
+/// use fullsend::message::InboundMessage;
+///
+/// # let form = "MessageSid=SM0123456789abcdef0123456789abcdef&From=%2B12025550189&To=%2B12025550123&Body=hi";
+/// let message = InboundMessage::from_form(form)?;
+/// # Ok::<(), fullsend::message::ParseError>(())
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct InboundMessage {
+    /// The body of the message. This is empty for media-only messages.
+    pub body: String,
+    /// The sender of the message.
+    pub from: String,
+    /// The URLs of the media attached to the message, in order.
+    pub media_urls: Vec<String>,
+    /// The SID of the message.
+    pub message_sid: Sid<sid::Message>,
+    /// The recipient of the message (i.e. your Twilio number).
+    pub to: String,
+}
+
+impl InboundMessage {
+    /// This function parses the form-encoded body of a Twilio inbound message
+    /// webhook request.
+    pub fn from_form(form: &str) -> Result<Self, ParseError> {
+        let mut fields: HashMap<_, _> = form_urlencoded::parse(form.as_bytes()).collect();
+        let mut take = |field: &str| fields.remove(field).map(Cow::into_owned);
+        let message_sid =
+            take("MessageSid").ok_or(ParseError::MissingField("MessageSid".into()))?;
+        let message_sid =
+            Sid::parse(&message_sid).map_err(|_| ParseError::InvalidField("MessageSid".into()))?;
+        let from = take("From").ok_or(ParseError::MissingField("From".into()))?;
+        let to = take("To").ok_or(ParseError::MissingField("To".into()))?;
+        let body = take("Body").unwrap_or_default();
+        // twilio tells us how many media URLs there are, then numbers them from
+        // zero
+        let num_media = match take("NumMedia") {
+            Some(num_media) => num_media
+                .parse()
+                .map_err(|_| ParseError::InvalidField("NumMedia".into()))?,
+            None => 0,
+        };
+        let mut media_urls = Vec::with_capacity(num_media);
+        for index in 0..num_media {
+            let field = format!("MediaUrl{}", index);
+            match take(&field) {
+                Some(media_url) => media_urls.push(media_url),
+                None => return Err(ParseError::MissingField(field)),
+            }
+        }
+        Ok(Self {
+            body,
+            from,
+            media_urls,
+            message_sid,
+            to,
+        })
+    }
+}
+
+/// The `MessageListFilter` struct picks which messages `Client::list_messages`
+/// lists. By default, every message is listed.
+///
+/// # Creating
+///
+/// ```rust
+/// use fullsend::message::MessageListFilter;
+///
+/// let filter = MessageListFilter::new()
+///     .to("+12025550189")
+///     .date_sent_after("2023-10-01")
+///     .page_size(100);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MessageListFilter<'a> {
+    date_sent: Option<&'a str>,
+    date_sent_after: Option<&'a str>,
+    date_sent_before: Option<&'a str>,
+    extra_query: Vec<(&'a str, &'a str)>,
+    from: Option<&'a str>,
+    page_size: Option<u32>,
+    to: Option<&'a str>,
+}
+
+impl<'a> MessageListFilter<'a> {
+    /// This function creates a `MessageListFilter` that doesn't filter out any
+    /// messages.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// This function returns the filter as the query params Twilio expects.
+    pub(crate) fn query_params(&self) -> Vec<(&'a str, Cow<'a, str>)> {
+        let mut params = Vec::with_capacity(6 + self.extra_query.len());
+        if let Some(to) = self.to {
+            params.push(("To", Cow::Borrowed(to)));
+        }
+        if let Some(from) = self.from {
+            params.push(("From", Cow::Borrowed(from)));
+        }
+        if let Some(date_sent) = self.date_sent {
+            params.push(("DateSent", Cow::Borrowed(date_sent)));
+        }
+        if let Some(date_sent_before) = self.date_sent_before {
+            params.push(("DateSent<", Cow::Borrowed(date_sent_before)));
+        }
+        if let Some(date_sent_after) = self.date_sent_after {
+            params.push(("DateSent>", Cow::Borrowed(date_sent_after)));
+        }
+        if let Some(page_size) = self.page_size {
+            params.push(("PageSize", Cow::Owned(page_size.to_string())));
+        }
+        // the extra params can't override the ones we know about
+        for (name, value) in &self.extra_query {
+            if !params.iter().any(|(known, _)| known == name) {
+                params.push((name, Cow::Borrowed(value)));
+            }
+        }
+        params
+    }
+
+    /// This function only lists messages sent on `date_sent`, formatted as
+    /// `YYYY-MM-DD`.
+    pub fn date_sent(mut self, date_sent: &'a str) -> Self {
+        self.date_sent = Some(date_sent);
+        self
+    }
+
+    /// This function only lists messages sent on or after `date_sent_after`,
+    /// formatted as `YYYY-MM-DD`.
+    pub fn date_sent_after(mut self, date_sent_after: &'a str) -> Self {
+        self.date_sent_after = Some(date_sent_after);
+        self
+    }
+
+    /// This function only lists messages sent on or before `date_sent_before`,
+    /// formatted as `YYYY-MM-DD`.
+    pub fn date_sent_before(mut self, date_sent_before: &'a str) -> Self {
+        self.date_sent_before = Some(date_sent_before);
+        self
+    }
+
+    /// This function adds a query param to the request that lists the
+    /// messages, for filters Twilio supports that `MessageListFilter` doesn't
+    /// have a function for yet. It can be called multiple times to add
+    /// multiple params. Params set with the other functions take precedence
+    /// over ones with the same name added with this one.
+    pub fn extra_query(mut self, name: &'a str, value: &'a str) -> Self {
+        self.extra_query.push((name, value));
+        self
+    }
+
+    /// This function only lists messages sent from `from`.
+    pub fn from(mut self, from: &'a str) -> Self {
+        self.from = Some(from);
+        self
+    }
+
+    /// This function sets how many messages Twilio returns per page, up to
+    /// 1000. Twilio returns 50 by default.
+    pub fn page_size(mut self, page_size: u32) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// This function only lists messages sent to `to`.
+    pub fn to(mut self, to: &'a str) -> Self {
+        self.to = Some(to);
+        self
+    }
+}
+
+/// The `MessagePage` struct represents a page of messages, as returned by
+/// `Client::list_messages`. Pass it to `Client::next_messages_page` to get the
+/// next page.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct MessagePage {
+    /// The messages on this page, newest first. This is empty if no messages
+    /// matched the filter.
+    pub messages: Vec<MessageResponse>,
+    /// The URI of the next page, relative to Twilio's API host, or `None` if
+    /// this is the last page.
+    #[serde(default)]
+    pub next_page_uri: Option<String>,
+}
+
+/// The `MessageResponse` struct represents a message as Twilio describes it,
+/// as returned by `Client::send_message` and `Client::fetch_message`.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct MessageResponse {
+    /// The body of the message, if it has one.
+    #[serde(default)]
+    pub body: Option<String>,
+    /// The fields Twilio sent that aren't modeled by this struct, like `uri`,
+    /// so that fields Twilio adds later can still be read.
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+    /// The sender of the message, if it has one.
+    #[serde(default)]
+    pub from: Option<String>,
+    /// The SID of the Messaging Service used to send the message, if one was
+    /// used.
+    #[serde(default)]
+    pub messaging_service_sid: Option<Sid<sid::MessagingService>>,
+    /// The number of segments the body was sent as, if Twilio has counted
+    /// them yet.
+    #[serde(default, deserialize_with = "deserialize_num_segments")]
+    pub num_segments: Option<u32>,
+    /// The price of the message, as formatted by Twilio. This is `None` until
+    /// the message has been billed.
+    #[serde(default)]
+    pub price: Option<String>,
+    /// The currency of `price`, like `USD`.
+    #[serde(default)]
+    pub price_unit: Option<String>,
+    /// The SID of the message.
+    pub sid: Sid<sid::Message>,
+    /// The status of the message.
+    pub status: MessageStatus,
+    /// The paths of the message's subresources, relative to
+    /// `https://api.twilio.com`, by name. For example, the path of its media
+    /// is under `media`.
+    #[serde(default)]
+    pub subresource_uris: HashMap<String, String>,
+    /// The recipient of the message.
+    #[serde(default)]
+    pub to: Option<String>,
+}
+
+impl MessageResponse {
+    /// This function reads a `MessageResponse` from Twilio's response to a
+    /// request to create or fetch a message, for sending requests with your
+    /// own HTTP client, like from a `PreparedRequest`. Unsuccessful responses
+    /// are turned into a `SendError::Twilio` error with the details Twilio
+    /// sent.
+    pub async fn from_response(response: reqwest::Response) -> Result<Self, SendError> {
+        if !response.status().is_success() {
+            return Err(client::twilio_error(response).await);
+        }
+        let body = match response.text().await {
+            Ok(body) => body,
+            Err(error) => return Err(SendError::Network(error)),
+        };
+        match serde_json::from_str(&body) {
+            Ok(message_response) => Ok(message_response),
+            Err(error) => Err(SendError::Json(error)),
+        }
+    }
+
+    /// This function returns a `MessageBuilder` filled in with the message's
+    /// recipient, sender, and body, for sending the message again, like after
+    /// it failed. If the message was sent through a Messaging Service, the
+    /// Messaging Service is used as the sender rather than the number it
+    /// picked, so that it picks one again.
+    pub fn to_builder(&self) -> MessageBuilder<'_> {
+        let mut builder = MessageBuilder::new();
+        if let Some(to) = self.to.as_deref() {
+            builder = builder.to(to);
+        }
+        builder = match (&self.messaging_service_sid, self.from.as_deref()) {
+            (Some(messaging_service_sid), _) => {
+                builder.messaging_service_sid(messaging_service_sid.as_str())
+            }
+            (None, Some(from)) => builder.from(from),
+            (None, None) => builder,
+        };
+        if let Some(body) = self.body.as_deref() {
+            builder = builder.body(body);
+        }
+        builder
+    }
+}
+
+/// This function deserializes the number of segments of a message, which
+/// Twilio sends as a string.
+fn deserialize_num_segments<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u32>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumSegments {
+        Number(u32),
+        String(String),
+    }
+    match Option::<NumSegments>::deserialize(deserializer)? {
+        Some(NumSegments::Number(num_segments)) => Ok(Some(num_segments)),
+        Some(NumSegments::String(num_segments)) => match num_segments.parse() {
+            Ok(num_segments) => Ok(Some(num_segments)),
+            Err(_) => Err(de::Error::custom("invalid num_segments")),
+        },
+        None => Ok(None),
+    }
+}
+
+/// The `ScheduledMessage` struct is a handle to a message scheduled with
+/// `MessageBuilder::scheduled_at`, as returned by `Client::send_scheduled`. It
+/// keeps the message's SID, so that the message can be canceled before it's
+/// sent without keeping track of the SID yourself.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScheduledMessage {
+    scheduled_at: DateTime<Utc>,
+    sid: Sid<sid::Message>,
+}
+
+impl ScheduledMessage {
+    /// This function creates a `ScheduledMessage` for the message with `sid`,
+    /// scheduled to be sent at `scheduled_at`.
+    pub fn new(sid: Sid<sid::Message>, scheduled_at: DateTime<Utc>) -> Self {
+        Self { scheduled_at, sid }
+    }
+
+    /// This function cancels the message with `client`, so that it isn't sent.
+    /// See `Client::cancel_message` for more.
+    pub async fn cancel(&self, client: &Client) -> Result<MessageResponse, SendError> {
+        client.cancel_message(self.sid.as_str()).await
+    }
+
+    /// This function returns when the message is scheduled to be sent.
+    pub fn scheduled_at(&self) -> DateTime<Utc> {
+        self.scheduled_at
+    }
+
+    /// This function returns the SID of the message.
+    pub fn sid(&self) -> &Sid<sid::Message> {
+        &self.sid
+    }
+}
+
+/// The `MessageStatus` enum represents the status of a Twilio message.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageStatus {
+    /// Twilio has accepted the message and is working out how to send it.
+    Accepted,
+    /// The message is scheduled to be sent later.
+    Scheduled,
+    /// The message is waiting to be sent.
+    Queued,
+    /// The message is being sent.
+    Sending,
+    /// The message has been sent to the carrier.
+    Sent,
+    /// The message couldn't be sent.
+    Failed,
+    /// The carrier confirmed that the message was delivered.
+    Delivered,
+    /// The carrier couldn't deliver the message.
+    Undelivered,
+    /// The recipient has read the message. Only some channels report this.
+    Read,
+    /// The message is being received.
+    Receiving,
+    /// The message has been received.
+    Received,
+    /// The scheduled message was canceled.
+    Canceled,
+    /// The message has a status that fullsend doesn't know about yet.
+    #[serde(other)]
+    Other,
+}
+
+impl MessageStatus {
+    /// This function returns whether the status is final, meaning the message
+    /// is done being sent and its status won't change, apart from a delivered
+    /// message being read. `delivered`, `undelivered`, `failed`, `canceled`,
+    /// `read`, and `received` are final, while `queued`, `sending`, `sent`,
+    /// and the like aren't.
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            MessageStatus::Canceled
+                | MessageStatus::Delivered
+                | MessageStatus::Failed
+                | MessageStatus::Read
+                | MessageStatus::Received
+                | MessageStatus::Undelivered
+        )
+    }
+}
+
+/// The `ParseError` enum represents the various types of errors that can arise
+/// when attempting to parse an `InboundMessage`.
+#[derive(Debug, thiserror::Error, PartialEq)]
+#[non_exhaustive]
+pub enum ParseError {
+    /// This error occurs when a field has a value that couldn't be parsed. The
+    /// name of the field is contained in this error.
+    #[error("field {0} is invalid")]
+    InvalidField(String),
+    /// This error occurs when a required field is missing. The name of the
+    /// field is contained in this error.
+    #[error("field {0} is missing")]
+    MissingField(String),
+}
+
+/// The prefix Twilio uses for WhatsApp addresses.
+const WHATSAPP_PREFIX: &str = "whatsapp:";
+
+/// This function adds the WhatsApp prefix to `address`, unless it already has
+/// it.
+fn with_whatsapp_prefix(address: Cow<'_, str>) -> Cow<'_, str> {
+    if address.starts_with(WHATSAPP_PREFIX) {
+        address
+    } else {
+        Cow::Owned(format!("{WHATSAPP_PREFIX}{address}"))
+    }
+}
+
+/// The header a message's idempotency key is sent in.
+const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// The maximum number of characters Twilio accepts in a message body.
+const MAX_BODY_CHARS: usize = 1600;
+
+/// The maximum number of media URLs Twilio accepts on a message.
+const MAX_MEDIA_URLS: usize = 10;
+
+/// How far in the past a message can be scheduled for before it's rejected,
+/// to allow for a local clock that's a little ahead of Twilio's.
+const SCHEDULE_SKEW: TimeDelta = TimeDelta::seconds(60);
+
+/// This function turns `cow` into one that owns its string, copying it if it's
+/// borrowed.
+fn owned(cow: Cow<'_, str>) -> Cow<'static, str> {
+    Cow::Owned(cow.into_owned())
+}
+
+/// This function returns whether `url` is an absolute `http` or `https` URL,
+/// the only kind Twilio can fetch media from.
+fn is_http_url(url: &str) -> bool {
+    match reqwest::Url::parse(url) {
+        Ok(url) => matches!(url.scheme(), "http" | "https") && url.has_host(),
+        Err(_) => false,
+    }
+}
+
+/// The characters in the GSM-7 basic character set.
+const GSM7_BASIC: &str = "@£$¥èéùìòÇ\nØø\rÅåΔ_ΦΓΛΩΠΨΣΘΞÆæßÉ !\"#¤%&'()*+,-./0123456789:;<=>?\
+    ¡ABCDEFGHIJKLMNOPQRSTUVWXYZÄÖÑÜ§¿abcdefghijklmnopqrstuvwxyzäöñüà";
+
+/// The characters in the GSM-7 extension table. These take two septets each.
+const GSM7_EXTENDED: &str = "\x0c^{}\\[~]|€";
+
+/// This function returns whether `address` is an E.164 phone number, like
+/// `+12025550189`, after any channel prefix like `whatsapp:`.
+fn is_e164(address: &str) -> bool {
+    let number = match address.rfind(':') {
+        Some(index) => &address[index + 1..],
+        None => address,
+    };
+    let digits = match number.strip_prefix('+') {
+        Some(digits) => digits,
+        None => return false,
+    };
+    (2..=15).contains(&digits.len())
+        && !digits.starts_with('0')
+        && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+/// This function returns whether `c` can be encoded with GSM-7.
+pub(crate) fn is_gsm7(c: char) -> bool {
+    GSM7_BASIC.contains(c) || GSM7_EXTENDED.contains(c)
+}
+
+/// This function counts the SMS segments `body` will be sent as. See
+/// `Message::body_segments` for how.
+pub(crate) fn segments(body: &str) -> u32 {
+    let (len, single, multi) = if body.chars().all(is_gsm7) {
+        let septets = body
+            .chars()
+            .map(|c| if GSM7_EXTENDED.contains(c) { 2 } else { 1 })
+            .sum();
+        (septets, 160, 153)
+    } else {
+        (body.encode_utf16().count(), 70, 67)
+    };
+    let segments = if len <= single {
+        1
+    } else {
+        len.div_ceil(multi)
+    };
+    segments as u32
+}
+
+/// This function masks a phone number so that it can be logged or displayed
+/// without revealing it in full. All but the last 4 digits are replaced with
+/// `*`, and any other characters (such as the leading `+`) are kept.
+///
+/// A channel prefix, such as `whatsapp:`, is kept as-is. Numbers with 4 or
+/// fewer digits are masked entirely, and alphanumeric sender IDs are returned
+/// unmasked, since they aren't phone numbers.
+///
+/// ```rust
+/// use fullsend::message::mask_number;
+///
+/// assert_eq!("+*******0189", mask_number("+12025550189"));
+/// assert_eq!("whatsapp:+*******0189", mask_number("whatsapp:+12025550189"));
+/// ```
+pub fn mask_number(number: &str) -> String {
+    // keep any channel prefix
+    let (prefix, address) = match number.rfind(':') {
+        Some(index) => number.split_at(index + 1),
+        None => ("", number),
+    };
+    // alphanumeric sender IDs aren't phone numbers, so there's nothing to hide
+    if address.chars().any(|c| c.is_alphabetic()) {
+        return number.into();
+    }
+    let num_digits = address.chars().filter(|c| c.is_ascii_digit()).count();
+    // if there are only a few digits, showing 4 of them would show them all
+    let num_masked = if num_digits > 4 {
+        num_digits - 4
+    } else {
+        num_digits
+    };
+    let mut masked = String::with_capacity(number.len());
+    masked.push_str(prefix);
+    let mut digits_seen = 0;
+    for c in address.chars() {
+        if c.is_ascii_digit() {
+            digits_seen += 1;
+            if digits_seen <= num_masked {
+                masked.push('*');
+                continue;
+            }
+        }
+        masked.push(c);
+    }
+    masked
+}
+
+/// This function splits `body` on word boundaries into parts of at most
+/// `max_chars` characters. Words longer than `max_chars` are split wherever
+/// they hit the limit.
+///
+/// If `numbered` is set and the body needs more than one part, each part is
+/// prefixed with its position, like `(1/3) `, and the prefix counts towards
+/// the limit. If the prefix wouldn't leave room for any of the body, the parts
+/// aren't numbered.
+pub(crate) fn split_body(body: &str, max_chars: usize, numbered: bool) -> Vec<Cow<'_, str>> {
+    let parts = split_words(body, max_chars);
+    if !numbered || parts.len() <= 1 {
+        return parts.into_iter().map(Cow::Borrowed).collect();
+    }
+    // the prefix's length depends on the number of parts, which depends on the
+    // prefix's length, so keep splitting until the two agree
+    let mut num_parts = parts.len();
+    loop {
+        let num_digits = num_parts.to_string().len();
+        // "(" + part + "/" + num_parts + ") "
+        let prefix_len = 2 * num_digits + 4;
+        if prefix_len >= max_chars {
+            return parts.into_iter().map(Cow::Borrowed).collect();
+        }
+        let parts = split_words(body, max_chars.saturating_sub(prefix_len));
+        if parts.len().to_string().len() <= num_digits {
+            let num_parts = parts.len();
+            return parts
+                .into_iter()
+                .enumerate()
+                .map(|(index, part)| Cow::Owned(format!("({}/{}) {}", index + 1, num_parts, part)))
+                .collect();
+        }
+        num_parts = parts.len();
+    }
+}
+
+/// This function truncates `body` to at most `max_chars` characters, ending it
+/// with `ellipsis` if anything was cut off. If `max_chars` is too small to fit
+/// the ellipsis, the body is cut off without one.
+fn truncate<'a>(body: Cow<'a, str>, max_chars: usize, ellipsis: &str) -> Cow<'a, str> {
+    if body.chars().count() <= max_chars {
+        return body;
+    }
+    let ellipsis_chars = ellipsis.chars().count();
+    let (keep, ellipsis) = if max_chars > ellipsis_chars {
+        (max_chars - ellipsis_chars, ellipsis)
+    } else {
+        (max_chars, "")
+    };
+    // we know there are more than `keep` chars, so this always finds one
+    let end = body
+        .char_indices()
+        .nth(keep)
+        .map_or(body.len(), |(end, _)| end);
+    Cow::Owned(format!("{}{}", &body[..end], ellipsis))
+}
+
+fn split_words(body: &str, max_chars: usize) -> Vec<&str> {
+    let max_chars = max_chars.max(1);
+    let mut parts = Vec::new();
+    let mut rest = body.trim();
+    while !rest.is_empty() {
+        // find the first character that wouldn't fit in this part
+        let (limit, next) = match rest.char_indices().nth(max_chars) {
+            Some(limit) => limit,
+            None => {
+                // everything left fits
+                parts.push(rest);
+                break;
+            }
+        };
+        // split at the last whitespace that keeps the part within the limit,
+        // or right at the limit if the word is too long to fit on its own
+        let split = match rest[..limit + next.len_utf8()].rfind(char::is_whitespace) {
+            Some(split) if split > 0 => split,
+            _ => limit,
+        };
+        parts.push(rest[..split].trim_end());
+        rest = rest[split..].trim_start();
+    }
+    parts
+}
+
+/// The `Channel` enum represents the channel a message is delivered over.
+///
+/// Twilio picks the channel from the message's addresses, so this is only used
+/// to validate the message against the channel's requirements.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Channel {
+    /// The message is delivered over SMS, or MMS if it has media.
+    #[default]
+    Sms,
+    /// The message is delivered over WhatsApp.
+    Whatsapp,
+}
+
+/// The `Content` enum represents the content of a message sent to many
+/// recipients with `Client::send_same_to_many`.
+#[derive(Debug, PartialEq)]
+pub enum Content<'a> {
+    /// The message is a freeform body.
+    Body(&'a str),
+    /// The message is a Twilio Content template, with optional Content
+    /// Variables.
+    Template {
+        /// The Twilio Content SID of the template.
+        content_sid: &'a str,
+        /// The Content Variables to fill the template with.
+        content_variables: Option<HashMap<&'a str, &'a str>>,
+    },
+}
+
+impl<'a> Content<'a> {
+    /// This function creates a `Message` with this content, sent from `from`.
+    /// It has no destination, so it's only a template for
+    /// `Client::send_same_to_many` to address to each recipient.
+    pub(crate) fn to_message(&self, from: &'a str) -> Message<'a> {
+        let (body, content_sid, content_variables) = match self {
+            Content::Body(body) => (Some(Cow::Borrowed(*body)), None, None),
+            Content::Template {
+                content_sid,
+                content_variables,
+            } => (
+                None,
+                Some(Cow::Borrowed(*content_sid)),
+                content_variables.as_ref().map(|content_variables| {
+                    content_variables
+                        .iter()
+                        .map(|(key, value)| (Cow::Borrowed(*key), Cow::Borrowed(*value)))
+                        .collect()
+                }),
+            ),
+        };
+        Message {
+            allow_body_with_content: false,
+            body,
+            channel: Channel::default(),
+            content_sid,
+            content_variables,
+            from: Some(Cow::Borrowed(from)),
+            headers: Vec::new(),
+            max_price: None,
+            media_urls: None,
+            messaging_service_sid: None,
+            require_e164: false,
+            require_gsm7: false,
+            scheduled_at: None,
+            shorten_urls: None,
+            smart_encoding: None,
+            status_callback: None,
+            status_callback_method: None,
+            to: Cow::Borrowed(""),
+            validity_period: None,
+        }
+    }
+}
+
+/// The `HttpMethod` enum represents the HTTP methods Twilio can use to call
+/// your webhooks.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HttpMethod {
+    /// The `GET` method.
+    Get,
+    /// The `POST` method.
+    Post,
+}
+
+impl HttpMethod {
+    /// This function returns the name of the method as Twilio expects it.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HttpMethod::Get => "GET",
+            HttpMethod::Post => "POST",
+        }
+    }
 }
 
 /// The `MessageBuilderError` enum represents the various types of errors that
 /// can arise when attempting to build a `MessageBuilder`.
 #[derive(Debug, thiserror::Error, PartialEq)]
+#[non_exhaustive]
 pub enum MessageBuilderError {
+    /// This error occurs when you attempt to build a `MessageBuilder` that
+    /// requires a GSM-7 body with a body containing characters that GSM-7
+    /// can't encode. The first such character is contained in this error.
+    #[error("body contains non-GSM-7 character {first_offending_char:?}")]
+    BodyNotGsm7 {
+        /// The first character in the body that GSM-7 can't encode.
+        first_offending_char: char,
+    },
+    /// This error occurs when the body of a message is longer than the 1600
+    /// characters Twilio accepts. The length of the body is contained in this
+    /// error.
+    #[error("body is {0} characters long, more than the maximum of 1600")]
+    BodyTooLong(usize),
+    /// This error occurs when you attempt to build a `MessageBuilder` with
+    /// both a body and a Twilio Content SID. The template provides the body,
+    /// so only one of them can be set, unless the builder allows both with
+    /// `allow_body_with_content`.
+    #[error("body and content SID can't both be set")]
+    BodyWithContentSid,
+    /// This error occurs when you attempt to build a `MessageBuilder` with
+    /// Content Variables but no Twilio Content SID, alongside a body or media.
+    /// The Content Variables would be ignored, since the `Client`'s default
+    /// Content SID is only used for messages without a body or media.
+    #[error("content variables require a content SID")]
+    ContentVariablesWithoutContentSid,
+    /// This error occurs when you attempt to build a `MessageBuilder` with a
+    /// media URL that isn't an absolute `http` or `https` URL, which Twilio
+    /// needs to fetch the media. The offending URL is contained in this error.
+    #[error("{0} isn't an http(s) URL")]
+    InvalidMediaUrl(String),
+    /// This error occurs when you attempt to build a `MessageBuilder` that
+    /// requires E.164 phone numbers with a `to` or `from` that isn't one. The
+    /// offending number is contained in this error.
+    #[error("{0} isn't an E.164 phone number")]
+    InvalidPhoneNumber(String),
+    /// This error occurs when you attempt to build a `MessageBuilder` for the
+    /// WhatsApp channel with media but without a body or Twilio Content SID.
+    /// WhatsApp requires media to be accompanied by a caption.
+    #[error("media requires a caption on this channel")]
+    MediaRequiresCaption,
+    /// This error occurs when you attempt to build a `MessageBuilder` where
+    /// only one of the `to` and `from` addresses has the `whatsapp:` prefix.
+    /// Both need it for the message to be sent over WhatsApp.
+    #[error("`to` and `from` are on different channels")]
+    MixedChannelAddresses,
+    /// This error occurs when you attempt to build a `MessageBuilder` with
+    /// localized bodies that have neither a body for the requested locale nor
+    /// for the default locale. The requested locale is contained in this
+    /// error.
+    #[error("no body for locale {0}")]
+    NoBodyForLocale(String),
     /// This error occurs when you attempt to build a `MessageBuilder` without
     /// setting a body for the message. This can be done by passing the desired
     /// message to the `body` function, passing a Twilio Content SID to the
     /// `content_sid` function, or passing URL(s) to the `media_url` function.
+    /// Passing Content Variables to the `content_variables` function is also
+    /// enough if the `Client` has a default Content SID.
     #[error("no message set in builder")]
     NoMessageSet,
     /// This error occurs when you attempt to build a `MessageBuilder` without
@@ -58,116 +1057,477 @@ pub enum MessageBuilderError {
     /// chain.
     #[error("no `to` field set in builder")]
     NoToSet,
+    /// This error occurs when you attempt to build a `MessageBuilder` with a
+    /// header that fullsend sets itself, such as `Authorization`. The name of
+    /// the header is contained in this error.
+    #[error("header {0} can't be set on a message")]
+    ReservedHeader(String),
+    /// This error occurs when you attempt to build a `MessageBuilder` that is
+    /// scheduled for a time that has already passed. The scheduled time and
+    /// the time the builder was built are contained in this error.
+    #[error("message is scheduled for {scheduled_at}, which is before {now}")]
+    ScheduleInPast {
+        /// The time the builder was built.
+        now: DateTime<Utc>,
+        /// The time the message was scheduled for.
+        scheduled_at: DateTime<Utc>,
+    },
+    /// This error occurs when you attempt to build a `MessageBuilder` that is
+    /// scheduled without setting a Twilio Messaging Service SID. Twilio can
+    /// only schedule messages sent through a Messaging Service.
+    #[error("scheduled messages require a messaging service")]
+    ScheduleRequiresMessagingService,
+    /// This error occurs when a message has more than the 10 media URLs Twilio
+    /// accepts. The number of media URLs is contained in this error.
+    #[error("message has {0} media URLs, more than the maximum of 10")]
+    TooManyMediaUrls(usize),
+}
+
+/// `MessageBuilderError` serializes as an object with the name of the variant
+/// and the error's message, for returning validation failures to API clients:
+///
+/// ```json
+/// { "error": "NoToSet", "message": "no `to` field set in builder" }
+/// ```
+impl Serialize for MessageBuilderError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let error = match self {
+            MessageBuilderError::BodyNotGsm7 { .. } => "BodyNotGsm7",
+            MessageBuilderError::BodyTooLong(_) => "BodyTooLong",
+            MessageBuilderError::BodyWithContentSid => "BodyWithContentSid",
+            MessageBuilderError::ContentVariablesWithoutContentSid => {
+                "ContentVariablesWithoutContentSid"
+            }
+            MessageBuilderError::InvalidMediaUrl(_) => "InvalidMediaUrl",
+            MessageBuilderError::InvalidPhoneNumber(_) => "InvalidPhoneNumber",
+            MessageBuilderError::MediaRequiresCaption => "MediaRequiresCaption",
+            MessageBuilderError::MixedChannelAddresses => "MixedChannelAddresses",
+            MessageBuilderError::NoBodyForLocale(_) => "NoBodyForLocale",
+            MessageBuilderError::NoMessageSet => "NoMessageSet",
+            MessageBuilderError::NoSenderSet => "NoSenderSet",
+            MessageBuilderError::NoToSet => "NoToSet",
+            MessageBuilderError::ReservedHeader(_) => "ReservedHeader",
+            MessageBuilderError::ScheduleInPast { .. } => "ScheduleInPast",
+            MessageBuilderError::ScheduleRequiresMessagingService => {
+                "ScheduleRequiresMessagingService"
+            }
+            MessageBuilderError::TooManyMediaUrls(_) => "TooManyMediaUrls",
+        };
+        let mut state = serializer.serialize_struct("MessageBuilderError", 2)?;
+        state.serialize_field("error", error)?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
 }
 
 /// The `MessageBuilder` struct is used to create a `Message`.
 #[derive(Default)]
 pub struct MessageBuilder<'a> {
-    body: Option<&'a str>,
-    content_sid: Option<&'a str>,
-    content_variables: Option<HashMap<&'a str, &'a str>>,
-    from: Option<&'a str>,
-    media_urls: Option<Vec<&'a str>>,
-    messaging_service_sid: Option<&'a str>,
-    to: Option<&'a str>,
+    allow_body_with_content: bool,
+    body: Option<Cow<'a, str>>,
+    channel: Channel,
+    content_sid: Option<Cow<'a, str>>,
+    content_variables: Option<HashMap<Cow<'a, str>, Cow<'a, str>>>,
+    default_locale: Option<Cow<'a, str>>,
+    from: Option<Cow<'a, str>>,
+    headers: Vec<(Cow<'a, str>, Cow<'a, str>)>,
+    idempotency_key: Option<Cow<'a, str>>,
+    locale: Option<Cow<'a, str>>,
+    localized_bodies: Option<HashMap<Cow<'a, str>, Cow<'a, str>>>,
+    max_price: Option<f64>,
+    media_urls: Option<Vec<Cow<'a, str>>>,
+    messaging_service_sid: Option<Cow<'a, str>>,
+    require_e164: bool,
+    require_gsm7: bool,
+    scheduled_at: Option<DateTime<Utc>>,
+    shorten_urls: Option<bool>,
+    smart_encoding: Option<bool>,
+    status_callback: Option<Cow<'a, str>>,
+    status_callback_method: Option<HttpMethod>,
+    to: Option<Cow<'a, str>>,
+    truncate_body: Option<usize>,
+    validity_period: Option<u32>,
 }
 
 impl<'a> MessageBuilder<'a> {
     /// This function creates a `MessageBuilder`.
     pub fn new() -> Self {
         Self {
+            allow_body_with_content: false,
             body: None,
+            channel: Channel::Sms,
             content_sid: None,
             content_variables: None,
+            default_locale: None,
             from: None,
+            headers: Vec::new(),
+            idempotency_key: None,
+            locale: None,
+            localized_bodies: None,
+            max_price: None,
             media_urls: None,
             messaging_service_sid: None,
+            require_e164: false,
+            require_gsm7: false,
+            scheduled_at: None,
+            shorten_urls: None,
+            smart_encoding: None,
+            status_callback: None,
+            status_callback_method: None,
             to: None,
+            truncate_body: None,
+            validity_period: None,
         }
     }
 
     /// This function validates the builder chain and returns a `Message` that
-    /// you can then use to interact with Twilio messages.
+    /// you can then use to interact with Twilio messages. Use `build_at` to
+    /// validate it against a time other than now.
     pub fn build(self) -> Result<Message<'a>, MessageBuilderError> {
+        self.build_at(SystemTime::now().into())
+    }
+
+    /// This function is `build`, but checks the message as if the current time
+    /// were `now`. Only the checks that depend on the time, like rejecting a
+    /// `scheduled_at` time that has already passed with
+    /// `MessageBuilderError::ScheduleInPast`, use it. This is useful for
+    /// testing code that schedules messages without depending on the clock.
+    ///
+    /// ```rust
+    /// use chrono::{DateTime, TimeDelta};
+    /// use fullsend::{message::MessageBuilderError, Message};
+    ///
+    /// let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+    /// let builder = || {
+    ///     Message::builder()
+    ///         .to("+12025550189")
+    ///         .messaging_service_sid("MG123")
+    ///         .body("hi")
+    /// };
+    /// let tomorrow = now + TimeDelta::days(1);
+    /// assert!(builder().scheduled_at(tomorrow).build_at(now).is_ok());
+    /// let yesterday = now - TimeDelta::days(1);
+    /// assert!(matches!(
+    ///     builder().scheduled_at(yesterday).build_at(now),
+    ///     Err(MessageBuilderError::ScheduleInPast { .. })
+    /// ));
+    /// ```
+    pub fn build_at(mut self, now: DateTime<Utc>) -> Result<Message<'a>, MessageBuilderError> {
+        // pick the body for the recipient's locale, falling back to the
+        // default locale if there isn't one
+        if let (Some(mut bodies), Some(locale)) = (self.localized_bodies.take(), self.locale) {
+            let body = bodies.remove(&locale).or_else(|| {
+                self.default_locale
+                    .as_ref()
+                    .and_then(|default| bodies.remove(default))
+            });
+            match body {
+                Some(body) => self.body = Some(body),
+                None => return Err(MessageBuilderError::NoBodyForLocale(locale.into_owned())),
+            }
+        }
+        // trim the body down to size first, so that it's validated as it'll be
+        // sent. the ellipsis isn't GSM-7, so use dots if GSM-7 is required
+        if let Some(max_chars) = self.truncate_body {
+            let ellipsis = if self.require_gsm7 { "..." } else { "…" };
+            self.body = self.body.map(|body| truncate(body, max_chars, ellipsis));
+        }
         // validate that a destination is set and unwrap it if it is
         let to = match self.to {
             Some(to) => to,
             None => return Err(MessageBuilderError::NoToSet),
         };
-        // validate that we have a sender: either a from or messaging service,
-        // or both
-        if self.from.is_none() && self.messaging_service_sid.is_none() {
-            return Err(MessageBuilderError::NoSenderSet);
-        }
-        // validate that we have content: any of body, media URL, or Content SID
-        if self.body.is_none() && self.media_urls.is_none() && self.content_sid.is_none() {
-            return Err(MessageBuilderError::NoMessageSet);
+        // infer the channel from the destination's prefix. the sender's prefix
+        // is checked against it with everything else
+        if to.starts_with(WHATSAPP_PREFIX) {
+            self.channel = Channel::Whatsapp;
         }
-        // all necessary fields are set, let's return the message
-        Ok(Message {
+        let message = Message {
+            allow_body_with_content: self.allow_body_with_content,
             body: self.body,
+            channel: self.channel,
             content_sid: self.content_sid,
             content_variables: self.content_variables,
             from: self.from,
+            headers: self
+                .headers
+                .into_iter()
+                .chain(
+                    self.idempotency_key
+                        .map(|key| (Cow::Borrowed(IDEMPOTENCY_KEY_HEADER), key)),
+                )
+                .collect(),
+            max_price: self.max_price,
             media_urls: self.media_urls,
             messaging_service_sid: self.messaging_service_sid,
+            require_e164: self.require_e164,
+            require_gsm7: self.require_gsm7,
+            scheduled_at: self.scheduled_at,
+            shorten_urls: self.shorten_urls,
+            smart_encoding: self.smart_encoding,
+            status_callback: self.status_callback,
+            status_callback_method: self.status_callback_method,
             to,
-        })
+            validity_period: self.validity_period,
+        };
+        // the message is only returned if it passes every check
+        match message.check(now).into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(message),
+        }
+    }
+
+    /// This function allows the message to have both a body and a Twilio
+    /// Content SID, which `build` otherwise rejects. Twilio uses the template
+    /// on channels that support it, like WhatsApp, and the body as a fallback
+    /// on channels that don't, like SMS.
+    pub fn allow_body_with_content(mut self) -> Self {
+        self.allow_body_with_content = true;
+        self
     }
 
     /// This function sets the content of the message (in this case, the body).
-    pub fn body(mut self, body: &'a str) -> Self {
-        self.body = Some(body);
+    pub fn body(mut self, body: impl Into<Cow<'a, str>>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// This function sets the body of the message to the one for `locale` in
+    /// `bodies`, which maps locales to bodies. If `bodies` doesn't have a body
+    /// for `locale`, the body for the locale passed to `default_locale` is used
+    /// instead. The body is picked when the builder is built, replacing any
+    /// body set with the `body` function.
+    pub fn body_localized<K, V>(
+        mut self,
+        bodies: HashMap<K, V>,
+        locale: impl Into<Cow<'a, str>>,
+    ) -> Self
+    where
+        K: Into<Cow<'a, str>>,
+        V: Into<Cow<'a, str>>,
+    {
+        let bodies = bodies
+            .into_iter()
+            .map(|(locale, body)| (locale.into(), body.into()))
+            .collect();
+        self.locale = Some(locale.into());
+        self.localized_bodies = Some(bodies);
+        self
+    }
+
+    /// This function sets the channel the message will be delivered over. This
+    /// is only used for validation: the channel is still picked by Twilio from
+    /// the message's addresses.
+    ///
+    /// If the message's addresses have the `whatsapp:` prefix, `build` sets the
+    /// channel to WhatsApp, so this doesn't need to be called.
+    pub fn channel(mut self, channel: Channel) -> Self {
+        self.channel = channel;
         self
     }
 
     /// This function sets the Twilio Content SID of the message
-    pub fn content_sid(mut self, content_sid: &'a str) -> Self {
-        self.content_sid = Some(content_sid);
+    pub fn content_sid(mut self, content_sid: impl Into<Cow<'a, str>>) -> Self {
+        self.content_sid = Some(content_sid.into());
         self
     }
 
     /// This function sets the Content Variables of the message.
-    pub fn content_variables(mut self, content_variables: HashMap<&'a str, &'a str>) -> Self {
+    ///
+    /// If the message has no other content, it's sent with the `Client`'s
+    /// default Content SID.
+    pub fn content_variables<K, V>(mut self, content_variables: HashMap<K, V>) -> Self
+    where
+        K: Into<Cow<'a, str>>,
+        V: Into<Cow<'a, str>>,
+    {
+        let content_variables = content_variables
+            .into_iter()
+            .map(|(name, value)| (name.into(), value.into()))
+            .collect();
         self.content_variables = Some(content_variables);
         self
     }
 
+    /// This function sets the locale whose body is used when the bodies passed
+    /// to `body_localized` have no body for the requested locale.
+    pub fn default_locale(mut self, default_locale: impl Into<Cow<'a, str>>) -> Self {
+        self.default_locale = Some(default_locale.into());
+        self
+    }
+
     /// This function sets the sender (in this case, the Twilio phone number
     /// you're using to send the message) of the message.
-    pub fn from(mut self, from: &'a str) -> Self {
-        self.from = Some(from);
+    pub fn from(mut self, from: impl Into<Cow<'a, str>>) -> Self {
+        self.from = Some(from.into());
+        self
+    }
+
+    /// This function sets the sender of the message to the WhatsApp account of
+    /// the phone number `from`, like `+14155238886`. The `whatsapp:` prefix is
+    /// added if `from` doesn't already have it.
+    pub fn from_whatsapp(mut self, from: impl Into<Cow<'a, str>>) -> Self {
+        self.from = Some(with_whatsapp_prefix(from.into()));
+        self
+    }
+
+    /// This function adds an HTTP header to the request that sends the
+    /// message. It can be called multiple times to add multiple headers. The
+    /// `Authorization` header can't be set.
+    pub fn header(mut self, name: impl Into<Cow<'a, str>>, value: impl Into<Cow<'a, str>>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// This function sets a key that identifies this message, so that sending
+    /// it more than once can be detected. The key is sent in the
+    /// `Idempotency-Key` header of the request, and every retry of the request
+    /// sends the same key.
+    ///
+    /// # Deduplication
+    ///
+    /// Twilio's Messages API doesn't deduplicate requests by this header
+    /// itself, so on its own this doesn't prevent duplicate messages. It's for
+    /// proxies and gateways in front of Twilio that do. Use a key that's unique
+    /// to the message, like the ID of the notification it's for, and reuse it
+    /// if you send the message again.
+    pub fn idempotency_key(mut self, idempotency_key: impl Into<Cow<'a, str>>) -> Self {
+        self.idempotency_key = Some(idempotency_key.into());
+        self
+    }
+
+    /// This function sets the most you're willing to pay for the message, in
+    /// the currency of your account. Twilio doesn't send the message if it
+    /// would cost more.
+    pub fn max_price(mut self, max_price: f64) -> Self {
+        self.max_price = Some(max_price);
         self
     }
 
-    /// This function sets the media URL of the message.
+    /// This function sets the media URL of the message, which must be a public
+    /// `http` or `https` URL.
     ///
     /// # Setting multiple media URLs
     ///
     /// This function will only store a single media URL. If you need to send
     /// multiple, use `media_urls` instead.
-    pub fn media_url(mut self, media_url: &'a str) -> Self {
-        self.media_urls = Some(vec![media_url]);
+    pub fn media_url(mut self, media_url: impl Into<Cow<'a, str>>) -> Self {
+        self.media_urls = Some(vec![media_url.into()]);
         self
     }
 
-    /// This function sets the media URLs of the message.
-    pub fn media_urls(mut self, media_urls: Vec<&'a str>) -> Self {
-        self.media_urls = Some(media_urls);
+    /// This function sets the media URLs of the message. Twilio accepts at most
+    /// 10, each of which must be a public `http` or `https` URL.
+    pub fn media_urls<U: Into<Cow<'a, str>>>(
+        mut self,
+        media_urls: impl IntoIterator<Item = U>,
+    ) -> Self {
+        self.media_urls = Some(media_urls.into_iter().map(Into::into).collect());
         self
     }
 
     /// This function sets the sender (in this case, the Twilio Messaging
     /// Service you're using to send the message) of the message.
-    pub fn messaging_service_sid(mut self, messaging_service_sid: &'a str) -> Self {
-        self.messaging_service_sid = Some(messaging_service_sid);
+    pub fn messaging_service_sid(mut self, messaging_service_sid: impl Into<Cow<'a, str>>) -> Self {
+        self.messaging_service_sid = Some(messaging_service_sid.into());
+        self
+    }
+
+    /// This function requires the `to` and `from` numbers of the message to be
+    /// in E.164 format, like `+12025550189`, which is the only format Twilio
+    /// accepts. If they aren't, `build` returns an error instead of Twilio
+    /// rejecting the message when it's sent.
+    ///
+    /// Channel prefixes like `whatsapp:` are allowed, and alphanumeric sender
+    /// IDs in `from` aren't checked.
+    pub fn require_e164(mut self) -> Self {
+        self.require_e164 = true;
+        self
+    }
+
+    /// This function requires the body of the message to be encodable with
+    /// GSM-7. If it isn't, `build` returns an error instead of letting the
+    /// message be sent as UCS-2, which would halve the characters that fit in
+    /// each segment.
+    pub fn require_gsm7(mut self) -> Self {
+        self.require_gsm7 = true;
+        self
+    }
+
+    /// This function schedules the message to be sent by Twilio at
+    /// `scheduled_at`. Twilio only accepts times a little while from now and
+    /// not too far in the future; see its documentation for the exact window.
+    /// `build` rejects times that have already passed. Scheduled messages
+    /// must be sent through a Messaging Service, so `messaging_service_sid`
+    /// must be set too.
+    ///
+    /// Send it with `Client::send_scheduled` to get a `ScheduledMessage` that
+    /// can cancel it before it's sent, or use `Client::cancel_message` with
+    /// its SID.
+    pub fn scheduled_at(mut self, scheduled_at: DateTime<Utc>) -> Self {
+        self.scheduled_at = Some(scheduled_at);
+        self
+    }
+
+    /// This function sets whether Twilio shortens the links in the body of the
+    /// message, so that it can track clicks on them. This requires the message
+    /// to be sent through a Messaging Service with link shortening set up.
+    pub fn shorten_urls(mut self, shorten_urls: bool) -> Self {
+        self.shorten_urls = Some(shorten_urls);
+        self
+    }
+
+    /// This function sets whether Twilio replaces characters in the body that
+    /// GSM-7 can't encode with similar ones it can, like curly quotes with
+    /// straight ones, so that the message isn't sent as UCS-2.
+    pub fn smart_encoding(mut self, smart_encoding: bool) -> Self {
+        self.smart_encoding = Some(smart_encoding);
+        self
+    }
+
+    /// This function sets the URL Twilio sends status updates about the
+    /// message to.
+    pub fn status_callback(mut self, status_callback: impl Into<Cow<'a, str>>) -> Self {
+        self.status_callback = Some(status_callback.into());
+        self
+    }
+
+    /// This function sets the HTTP method Twilio uses to send status updates
+    /// to the URL set with `status_callback`. Twilio uses `POST` if this isn't
+    /// set.
+    pub fn status_callback_method(mut self, status_callback_method: HttpMethod) -> Self {
+        self.status_callback_method = Some(status_callback_method);
         self
     }
 
     /// This function sets the destination (i.e. recipient's phone number) of
     /// the message.
-    pub fn to(mut self, to: &'a str) -> Self {
-        self.to = Some(to);
+    pub fn to(mut self, to: impl Into<Cow<'a, str>>) -> Self {
+        self.to = Some(to.into());
+        self
+    }
+
+    /// This function sets the destination of the message to the WhatsApp
+    /// account of the phone number `to`, like `+12025550189`. The `whatsapp:`
+    /// prefix is added if `to` doesn't already have it.
+    pub fn to_whatsapp(mut self, to: impl Into<Cow<'a, str>>) -> Self {
+        self.to = Some(with_whatsapp_prefix(to.into()));
+        self
+    }
+
+    /// This function sets the maximum length of the body in characters. If the
+    /// body is longer, `build` truncates it to that length, ending it with an
+    /// ellipsis (`…`, or `...` if `require_gsm7` is set) that counts towards
+    /// the limit.
+    pub fn truncate_body(mut self, max_chars: usize) -> Self {
+        self.truncate_body = Some(max_chars);
+        self
+    }
+
+    /// This function sets how many seconds the message may wait in Twilio's
+    /// queue before being sent. If it can't be sent in time, it fails instead.
+    pub fn validity_period(mut self, validity_period: u32) -> Self {
+        self.validity_period = Some(validity_period);
         self
     }
 }
@@ -176,6 +1536,353 @@ impl<'a> MessageBuilder<'a> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn body_len_counts_bytes_and_chars() {
+        let message = Message::builder()
+            .to("")
+            .from("")
+            .body("héllo 👋")
+            .build()
+            .unwrap();
+        assert_eq!(Some(11), message.body_byte_len());
+        assert_eq!(Some(7), message.body_char_len());
+    }
+
+    #[test]
+    fn body_segments_depend_on_encoding() {
+        assert_eq!(1, segments(&"a".repeat(160)));
+        assert_eq!(2, segments(&"a".repeat(161)));
+        assert_eq!(1, segments(&"€".repeat(80)));
+        assert_eq!(2, segments(&"€".repeat(81)));
+        assert_eq!(1, segments(&"é".repeat(160)));
+        assert_eq!(1, segments(&"ä½".repeat(35)));
+        assert_eq!(3, segments(&"ä½".repeat(68)));
+        let message = Message::builder()
+            .to("")
+            .from("")
+            .body("hi")
+            .build()
+            .unwrap();
+        assert_eq!(Some(1), message.body_segments());
+    }
+
+    #[test]
+    fn body_len_is_none_without_body() {
+        let message = Message::builder()
+            .to("")
+            .from("")
+            .media_url("https://example.com/a.png")
+            .build()
+            .unwrap();
+        assert_eq!(None, message.body_byte_len());
+        assert_eq!(None, message.body_char_len());
+    }
+
+    #[test]
+    fn body_localized_picks_locale() {
+        let bodies = HashMap::from([("en", "hello"), ("fr", "bonjour")]);
+        let message = Message::builder()
+            .to("")
+            .from("")
+            .body_localized(bodies, "fr")
+            .default_locale("en")
+            .build()
+            .unwrap();
+        assert_eq!(Some("bonjour"), message.body.as_deref());
+    }
+
+    #[test]
+    fn body_localized_falls_back_to_default_locale() {
+        let bodies = HashMap::from([("en", "hello"), ("fr", "bonjour")]);
+        let message = Message::builder()
+            .to("")
+            .from("")
+            .body_localized(bodies.clone(), "de")
+            .default_locale("en")
+            .build()
+            .unwrap();
+        assert_eq!(Some("hello"), message.body.as_deref());
+        let builder_result = Message::builder()
+            .to("")
+            .from("")
+            .body_localized(bodies, "de")
+            .build();
+        assert_eq!(
+            Err(MessageBuilderError::NoBodyForLocale("de".to_string())),
+            builder_result
+        );
+    }
+
+    #[test]
+    fn builder_rejects_invalid_media_urls() {
+        let builder_result = Message::builder()
+            .to("")
+            .from("")
+            .media_url("/a.png")
+            .build();
+        assert_eq!(
+            Err(MessageBuilderError::InvalidMediaUrl("/a.png".into())),
+            builder_result
+        );
+        let builder_result = Message::builder()
+            .to("")
+            .from("")
+            .media_url("ftp://example.com/a.png")
+            .build();
+        assert_eq!(
+            Err(MessageBuilderError::InvalidMediaUrl(
+                "ftp://example.com/a.png".into()
+            )),
+            builder_result
+        );
+        let builder_result = Message::builder()
+            .to("")
+            .from("")
+            .media_urls(vec!["https://example.com/a.png"; 11])
+            .build();
+        assert_eq!(
+            Err(MessageBuilderError::TooManyMediaUrls(11)),
+            builder_result
+        );
+    }
+
+    #[test]
+    fn builder_accepts_owned_strings() {
+        let to = String::from("+12025550189");
+        let message = Message::builder()
+            .to(&to)
+            .from(String::from("+12025550123"))
+            .body(format!("hi {}", "Bob"))
+            .build()
+            .unwrap();
+        assert_eq!("+12025550189", message.to);
+        assert_eq!(Some("+12025550123"), message.from.as_deref());
+        assert_eq!(Some("hi Bob"), message.body.as_deref());
+    }
+
+    #[test]
+    fn builder_accepts_owned_maps_and_headers() {
+        // owned inputs don't borrow from anything, so the message is 'static
+        // without needing into_owned
+        let message: OwnedMessage = Message::builder()
+            .to("+12025550189")
+            .from("+12025550123")
+            .body_localized(
+                HashMap::from([(String::from("fr"), format!("bonjour {}", "Bob"))]),
+                String::from("de"),
+            )
+            .default_locale(String::from("fr"))
+            .header(String::from("X-Request-Id"), 42.to_string())
+            .build()
+            .unwrap();
+        assert_eq!(Some("bonjour Bob"), message.body.as_deref());
+        assert_eq!(
+            vec![(Cow::Borrowed("X-Request-Id"), Cow::Borrowed("42"))],
+            message.headers
+        );
+        let message: OwnedMessage = Message::builder()
+            .to("+12025550189")
+            .from("+12025550123")
+            .content_sid("HX123")
+            .content_variables(HashMap::from([("name", String::from("Bob"))]))
+            .build()
+            .unwrap();
+        assert_eq!(
+            Some(&Cow::Borrowed("Bob")),
+            message
+                .content_variables
+                .as_ref()
+                .and_then(|content_variables| content_variables.get("name"))
+        );
+    }
+
+    #[test]
+    fn builder_rejects_body_with_content_sid() {
+        let builder_result = Message::builder()
+            .to("")
+            .from("")
+            .body("hi")
+            .content_sid("HX123")
+            .build();
+        assert_eq!(Err(MessageBuilderError::BodyWithContentSid), builder_result);
+        let message = Message::builder()
+            .to("")
+            .from("")
+            .body("hi")
+            .content_sid("HX123")
+            .allow_body_with_content()
+            .build()
+            .unwrap();
+        assert_eq!(Some("hi"), message.body.as_deref());
+        assert_eq!(Some("HX123"), message.content_sid.as_deref());
+    }
+
+    #[test]
+    fn builder_rejects_content_variables_without_content_sid() {
+        let builder_result = Message::builder()
+            .to("")
+            .from("")
+            .body("hi")
+            .content_variables([("name", "Bob")].into())
+            .build();
+        assert_eq!(
+            Err(MessageBuilderError::ContentVariablesWithoutContentSid),
+            builder_result
+        );
+        let builder_result = Message::builder()
+            .to("")
+            .from("")
+            .media_url("https://example.com/a.png")
+            .content_variables([("name", "Bob")].into())
+            .build();
+        assert_eq!(
+            Err(MessageBuilderError::ContentVariablesWithoutContentSid),
+            builder_result
+        );
+        // on their own, they're used with the client's default content SID
+        let builder_result = Message::builder()
+            .to("")
+            .from("")
+            .content_variables([("name", "Bob")].into())
+            .build();
+        assert!(builder_result.is_ok());
+    }
+
+    #[test]
+    fn builder_allows_media_only_sms() {
+        let builder_result = Message::builder()
+            .to("")
+            .from("")
+            .media_url("https://example.com/a.png")
+            .build();
+        assert!(builder_result.is_ok());
+    }
+
+    #[test]
+    fn builder_requires_caption_for_whatsapp_media() {
+        let builder_result = Message::builder()
+            .to("")
+            .from("")
+            .media_url("https://example.com/a.png")
+            .channel(Channel::Whatsapp)
+            .build();
+        assert_eq!(
+            Err(MessageBuilderError::MediaRequiresCaption),
+            builder_result
+        );
+    }
+
+    #[test]
+    fn builder_requires_e164_numbers_when_asked() {
+        let builder = || Message::builder().body("hi").require_e164();
+        assert!(builder()
+            .to("+12025550189")
+            .from("+12025550123")
+            .build()
+            .is_ok());
+        assert!(builder()
+            .to("whatsapp:+12025550189")
+            .from("whatsapp:+12025550123")
+            .build()
+            .is_ok());
+        assert!(builder()
+            .to("+12025550189")
+            .from("Fullsend")
+            .build()
+            .is_ok());
+        assert_eq!(
+            Err(MessageBuilderError::InvalidPhoneNumber("2025550189".into())),
+            builder().to("2025550189").from("+12025550123").build()
+        );
+        assert_eq!(
+            Err(MessageBuilderError::InvalidPhoneNumber("+0123".into())),
+            builder().to("+12025550189").from("+0123").build()
+        );
+        assert!(Message::builder()
+            .to("2025550189")
+            .from("")
+            .body("hi")
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn builder_requires_gsm7_body_when_asked() {
+        let builder_result = Message::builder()
+            .to("")
+            .from("")
+            .body("it’s €5 — thanks")
+            .require_gsm7()
+            .build();
+        assert_eq!(
+            Err(MessageBuilderError::BodyNotGsm7 {
+                first_offending_char: '’'
+            }),
+            builder_result
+        );
+        let builder_result = Message::builder()
+            .to("")
+            .from("")
+            .body("it's €5 [not £5] - thanks!")
+            .require_gsm7()
+            .build();
+        assert!(builder_result.is_ok());
+    }
+
+    #[test]
+    fn builder_rejects_authorization_header() {
+        let builder_result = Message::builder()
+            .to("")
+            .from("")
+            .body("")
+            .header("X-Request-Id", "1")
+            .header("authorization", "Basic Zm9vOmJhcg==")
+            .build();
+        assert_eq!(
+            Err(MessageBuilderError::ReservedHeader("authorization".into())),
+            builder_result
+        );
+    }
+
+    #[test]
+    fn builder_infers_whatsapp_channel() {
+        let builder_result = Message::builder()
+            .to("whatsapp:+12025550189")
+            .from("whatsapp:+12025550123")
+            .media_url("https://example.com/a.png")
+            .build();
+        assert_eq!(
+            Err(MessageBuilderError::MediaRequiresCaption),
+            builder_result
+        );
+    }
+
+    #[test]
+    fn builder_whatsapp_helpers_add_prefix_once() {
+        let message = Message::builder()
+            .to_whatsapp("+12025550189")
+            .from_whatsapp("whatsapp:+14155238886")
+            .body("hi")
+            .build()
+            .unwrap();
+        assert_eq!("whatsapp:+12025550189", message.to);
+        assert_eq!(Some("whatsapp:+14155238886"), message.from.as_deref());
+        assert_eq!(Channel::Whatsapp, message.channel);
+    }
+
+    #[test]
+    fn builder_rejects_mixed_channel_addresses() {
+        let builder_result = Message::builder()
+            .to("whatsapp:+12025550189")
+            .from("+12025550123")
+            .body("")
+            .build();
+        assert_eq!(
+            Err(MessageBuilderError::MixedChannelAddresses),
+            builder_result
+        );
+    }
+
     #[test]
     fn builder_requires_message() {
         let builder_result = Message::builder().to("").from("").build();
@@ -194,6 +1901,397 @@ mod tests {
         assert_eq!(Err(MessageBuilderError::NoToSet), builder_result);
     }
 
+    #[test]
+    fn builder_error_serializes_with_name_and_message() {
+        let json = serde_json::to_string(&MessageBuilderError::NoToSet).unwrap();
+        assert_eq!(
+            r#"{"error":"NoToSet","message":"no `to` field set in builder"}"#,
+            json
+        );
+    }
+
+    #[test]
+    fn inbound_message_collects_media_urls() {
+        let form = "MessageSid=MM0123456789abcdef0123456789abcdef&From=%2B12025550189\
+            &To=%2B12025550123&Body=&NumMedia=2&MediaUrl1=https%3A%2F%2Fexample.com%2F1\
+            &MediaUrl0=https%3A%2F%2Fexample.com%2F0";
+        let message = InboundMessage::from_form(form).unwrap();
+        assert_eq!("+12025550189", message.from);
+        assert_eq!("", message.body);
+        assert_eq!(
+            vec!["https://example.com/0", "https://example.com/1"],
+            message.media_urls
+        );
+    }
+
+    #[test]
+    fn inbound_message_requires_listed_media() {
+        let form = "MessageSid=MM0123456789abcdef0123456789abcdef&From=%2B12025550189\
+            &To=%2B12025550123&NumMedia=1";
+        assert_eq!(
+            Err(ParseError::MissingField("MediaUrl0".into())),
+            InboundMessage::from_form(form)
+        );
+    }
+
+    #[test]
+    fn idempotency_key_is_sent_as_header() {
+        let message = Message::builder()
+            .to("")
+            .from("")
+            .body("hi")
+            .idempotency_key(String::from("order-42-shipped"))
+            .build()
+            .unwrap();
+        assert_eq!(
+            vec![(
+                Cow::Borrowed("Idempotency-Key"),
+                Cow::Borrowed("order-42-shipped")
+            )],
+            message.headers
+        );
+    }
+
+    #[test]
+    fn into_owned_outlives_builder_inputs() {
+        let message: OwnedMessage = {
+            let to = String::from("+12025550189");
+            let name = String::from("Bob");
+            Message::builder()
+                .to(&to)
+                .from("+12025550123")
+                .content_sid("HX123")
+                .content_variables([("name", name.as_str())].into())
+                .header("X-Request-Id", &name)
+                .build()
+                .unwrap()
+                .into_owned()
+        };
+        assert_eq!("+12025550189", message.to);
+        assert_eq!(
+            Some(&Cow::Borrowed("Bob")),
+            message
+                .content_variables
+                .as_ref()
+                .and_then(|content_variables| content_variables.get("name"))
+        );
+        assert_eq!(
+            vec![(Cow::Borrowed("X-Request-Id"), Cow::Borrowed("Bob"))],
+            message.headers
+        );
+    }
+
+    #[test]
+    fn mask_number_keeps_last_four_digits() {
+        assert_eq!("+*******0189", mask_number("+12025550189"));
+        assert_eq!("+* (***) ***-0189", mask_number("+1 (202) 555-0189"));
+    }
+
+    #[test]
+    fn mask_number_masks_short_numbers_entirely() {
+        assert_eq!("*2345", mask_number("12345"));
+        assert_eq!("****", mask_number("1234"));
+    }
+
+    #[test]
+    fn mask_number_passes_through_alphanumeric_sender_ids() {
+        assert_eq!("fullsend", mask_number("fullsend"));
+        assert_eq!(
+            "whatsapp:+*******0189",
+            mask_number("whatsapp:+12025550189")
+        );
+    }
+
+    #[test]
+    fn message_status_is_final_once_done_sending() {
+        assert!(!MessageStatus::Queued.is_final());
+        assert!(!MessageStatus::Sending.is_final());
+        assert!(!MessageStatus::Sent.is_final());
+        assert!(MessageStatus::Delivered.is_final());
+        assert!(MessageStatus::Undelivered.is_final());
+        assert!(MessageStatus::Failed.is_final());
+        assert!(MessageStatus::Canceled.is_final());
+    }
+
+    #[test]
+    fn message_list_filter_builds_query_params() {
+        let filter = MessageListFilter::new()
+            .to("+12025550189")
+            .date_sent_before("2023-10-31")
+            .page_size(20);
+        assert_eq!(
+            vec![
+                ("To", Cow::Borrowed("+12025550189")),
+                ("DateSent<", Cow::Borrowed("2023-10-31")),
+                ("PageSize", Cow::Borrowed("20")),
+            ],
+            filter.query_params()
+        );
+        assert!(MessageListFilter::new().query_params().is_empty());
+    }
+
+    #[test]
+    fn message_list_filter_adds_extra_query_params() {
+        let filter = MessageListFilter::new()
+            .to("+12025550189")
+            .extra_query("To", "+12025550123")
+            .extra_query("PageToken", "PAabc");
+        assert_eq!(
+            vec![
+                ("To", Cow::Borrowed("+12025550189")),
+                ("PageToken", Cow::Borrowed("PAabc")),
+            ],
+            filter.query_params()
+        );
+    }
+
+    #[test]
+    fn message_page_deserializes_empty_page() {
+        let json = r#"{ "messages": [], "next_page_uri": null, "page": 0 }"#;
+        let page: MessagePage = serde_json::from_str(json).unwrap();
+        assert!(page.messages.is_empty());
+        assert_eq!(None, page.next_page_uri);
+    }
+
+    #[test]
+    fn message_response_deserializes() {
+        let json = r#"{
+            "body": "hi",
+            "from": "+12025550123",
+            "messaging_service_sid": null,
+            "num_segments": "2",
+            "price": null,
+            "price_unit": "USD",
+            "sid": "SM0123456789abcdef0123456789abcdef",
+            "status": "queued",
+            "subresource_uris": {
+                "media": "/2010-04-01/Accounts/AC0123456789abcdef0123456789abcdef/Messages/SM0123456789abcdef0123456789abcdef/Media.json"
+            },
+            "to": "+12025550189",
+            "uri": "/2010-04-01/Accounts/AC0123456789abcdef0123456789abcdef/Messages.json"
+        }"#;
+        let response: MessageResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(MessageStatus::Queued, response.status);
+        assert_eq!(Some(2), response.num_segments);
+        assert_eq!(None, response.price);
+        assert_eq!(
+            Some(&serde_json::json!(
+                "/2010-04-01/Accounts/AC0123456789abcdef0123456789abcdef/Messages.json"
+            )),
+            response.extra.get("uri")
+        );
+        assert!(!response.extra.contains_key("sid"));
+        assert_eq!(
+            Some("/2010-04-01/Accounts/AC0123456789abcdef0123456789abcdef/Messages/SM0123456789abcdef0123456789abcdef/Media.json"),
+            response.subresource_uris.get("media").map(String::as_str)
+        );
+    }
+
+    #[test]
+    fn message_response_from_response_checks_status() {
+        let response = http::Response::builder()
+            .status(201)
+            .body(r#"{ "sid": "SM0123456789abcdef0123456789abcdef", "status": "queued" }"#)
+            .unwrap();
+        let message_response =
+            tokio_test::block_on(MessageResponse::from_response(response.into())).unwrap();
+        assert_eq!(MessageStatus::Queued, message_response.status);
+        let response = http::Response::builder()
+            .status(400)
+            .body(r#"{ "code": 21211, "message": "Invalid 'To' Phone Number" }"#)
+            .unwrap();
+        let error =
+            tokio_test::block_on(MessageResponse::from_response(response.into())).unwrap_err();
+        assert_eq!(Some(400), error.status());
+        assert_eq!(Some(21211), error.twilio_code());
+    }
+
+    #[test]
+    fn message_response_to_builder_prefills_message() {
+        let json = r#"{
+            "body": "hi",
+            "from": "+12025550123",
+            "messaging_service_sid": null,
+            "sid": "SM0123456789abcdef0123456789abcdef",
+            "status": "failed",
+            "to": "+12025550189"
+        }"#;
+        let response: MessageResponse = serde_json::from_str(json).unwrap();
+        let message = response.to_builder().build().unwrap();
+        assert_eq!("+12025550189", message.to);
+        assert_eq!(Some("+12025550123"), message.from.as_deref());
+        assert_eq!(None, message.messaging_service_sid);
+        assert_eq!(Some("hi"), message.body.as_deref());
+        let json = r#"{
+            "body": "hi",
+            "from": "+12025550123",
+            "messaging_service_sid": "MG0123456789abcdef0123456789abcdef",
+            "sid": "SM0123456789abcdef0123456789abcdef",
+            "status": "failed",
+            "to": "+12025550189"
+        }"#;
+        let response: MessageResponse = serde_json::from_str(json).unwrap();
+        let message = response.to_builder().build().unwrap();
+        assert_eq!(None, message.from);
+        assert_eq!(
+            Some("MG0123456789abcdef0123456789abcdef"),
+            message.messaging_service_sid.as_deref()
+        );
+    }
+
+    #[test]
+    fn message_response_tolerates_missing_fields() {
+        let json =
+            r#"{ "sid": "SM0123456789abcdef0123456789abcdef", "status": "partially_delivered" }"#;
+        let response: MessageResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(MessageStatus::Other, response.status);
+        assert_eq!(None, response.num_segments);
+    }
+
+    #[test]
+    fn scheduled_message_requires_messaging_service() {
+        let scheduled_at = DateTime::from_timestamp(4_102_444_800, 0).unwrap();
+        let builder_result = Message::builder()
+            .to("")
+            .from("")
+            .body("hi")
+            .scheduled_at(scheduled_at)
+            .build();
+        assert_eq!(
+            Err(MessageBuilderError::ScheduleRequiresMessagingService),
+            builder_result
+        );
+        let message = Message::builder()
+            .to("")
+            .messaging_service_sid("MG123")
+            .body("hi")
+            .scheduled_at(scheduled_at)
+            .build()
+            .unwrap();
+        assert_eq!(Some(scheduled_at), message.scheduled_at);
+    }
+
+    #[test]
+    fn scheduled_message_must_be_in_future() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let builder = || {
+            Message::builder()
+                .to("")
+                .messaging_service_sid("MG123")
+                .body("hi")
+        };
+        let scheduled_at = now - TimeDelta::minutes(5);
+        assert_eq!(
+            Err(MessageBuilderError::ScheduleInPast { now, scheduled_at }),
+            builder().scheduled_at(scheduled_at).build_at(now)
+        );
+        // a little skew is tolerated
+        let scheduled_at = now - TimeDelta::seconds(30);
+        assert!(builder().scheduled_at(scheduled_at).build_at(now).is_ok());
+        let scheduled_at = now + TimeDelta::minutes(30);
+        assert!(builder().scheduled_at(scheduled_at).build_at(now).is_ok());
+    }
+
+    #[test]
+    fn split_body_splits_on_words() {
+        let parts = split_body("the quick brown fox jumps", 10, false);
+        assert_eq!(vec!["the quick", "brown fox", "jumps"], parts);
+    }
+
+    #[test]
+    fn split_body_splits_long_words() {
+        let parts = split_body("abcdefghij klm", 4, false);
+        assert_eq!(vec!["abcd", "efgh", "ij", "klm"], parts);
+    }
+
+    #[test]
+    fn split_body_numbers_parts() {
+        let parts = split_body("the quick brown fox jumps", 16, true);
+        assert_eq!(
+            vec!["(1/3) the quick", "(2/3) brown fox", "(3/3) jumps"],
+            parts
+        );
+        let parts = split_body("short", 16, true);
+        assert_eq!(vec!["short"], parts);
+    }
+
+    #[test]
+    fn split_body_skips_numbers_that_dont_fit() {
+        // "(1/9) " alone is longer than the limit
+        let parts = split_body("the quick brown fox", 4, true);
+        assert_eq!(vec!["the", "quic", "k", "brow", "n", "fox"], parts);
+    }
+
+    #[test]
+    fn validate_limits_reports_every_problem() {
+        let body = "a".repeat(1601);
+        let message = Message::builder()
+            .to("+12025550189")
+            .messaging_service_sid("MG123")
+            .body(&body)
+            .build()
+            .unwrap();
+        assert_eq!(
+            Err(vec![MessageBuilderError::BodyTooLong(1601)]),
+            message.validate_limits()
+        );
+        // the builder wouldn't allow any of these
+        let mut media_urls = vec![Cow::Borrowed("https://example.com/a.png"); 10];
+        media_urls.push("ftp://example.com/b.png".into());
+        let message = Message {
+            content_sid: Some("HX123".into()),
+            headers: vec![("Authorization".into(), "Basic Zm9vOmJhcg==".into())],
+            media_urls: Some(media_urls),
+            require_e164: true,
+            to: "2025550189".into(),
+            ..message
+        };
+        assert_eq!(
+            Err(vec![
+                MessageBuilderError::BodyTooLong(1601),
+                MessageBuilderError::BodyWithContentSid,
+                MessageBuilderError::TooManyMediaUrls(11),
+                MessageBuilderError::InvalidMediaUrl("ftp://example.com/b.png".into()),
+                MessageBuilderError::ReservedHeader("Authorization".into()),
+                MessageBuilderError::InvalidPhoneNumber("2025550189".into()),
+            ]),
+            message.validate_limits()
+        );
+    }
+
+    #[test]
+    fn truncate_body_truncates_long_bodies() {
+        let message = Message::builder()
+            .to("")
+            .from("")
+            .body("héllo wörld")
+            .truncate_body(8)
+            .build()
+            .unwrap();
+        assert_eq!(Some("héllo w…"), message.body.as_deref());
+        let message = Message::builder()
+            .to("")
+            .from("")
+            .body("hello world")
+            .truncate_body(8)
+            .require_gsm7()
+            .build()
+            .unwrap();
+        assert_eq!(Some("hello..."), message.body.as_deref());
+    }
+
+    #[test]
+    fn truncate_body_leaves_short_bodies() {
+        let message = Message::builder()
+            .to("")
+            .from("")
+            .body("hello")
+            .truncate_body(5)
+            .build()
+            .unwrap();
+        assert_eq!(Some("hello"), message.body.as_deref());
+    }
+
     #[test]
     fn valid_builder_returns_message() {
         let message = Message::builder().to("").from("").body("").build();
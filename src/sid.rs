@@ -0,0 +1,206 @@
+//! This module provides a typed interface for Twilio SIDs.
+//!
+//! Every Twilio resource is identified by a SID: a two-letter prefix that
+//! identifies the kind of resource, followed by 32 alphanumeric characters.
+//! `Sid` is parameterized by a marker kind, so that, for example, a message
+//! SID can't be passed where a messaging service SID is expected.
+
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
+
+use serde::{de, Deserialize, Deserializer};
+
+/// The `SidKind` trait is implemented by the marker types that identify the
+/// kind of a `Sid`.
+pub trait SidKind {
+    /// The two-letter prefixes that a SID of this kind can start with.
+    const PREFIXES: &'static [&'static str];
+}
+
+/// The marker kind for account SIDs (`AC`).
+#[derive(Debug)]
+pub enum Account {}
+
+/// The marker kind for API key SIDs (`SK`).
+#[derive(Debug)]
+pub enum ApiKey {}
+
+/// The marker kind for Content template SIDs (`HX`).
+#[derive(Debug)]
+pub enum Content {}
+
+/// The marker kind for media SIDs (`ME`).
+#[derive(Debug)]
+pub enum Media {}
+
+/// The marker kind for message SIDs (`SM` for SMS, `MM` for MMS).
+#[derive(Debug)]
+pub enum Message {}
+
+/// The marker kind for Messaging Service SIDs (`MG`).
+#[derive(Debug)]
+pub enum MessagingService {}
+
+impl SidKind for Account {
+    const PREFIXES: &'static [&'static str] = &["AC"];
+}
+
+impl SidKind for ApiKey {
+    const PREFIXES: &'static [&'static str] = &["SK"];
+}
+
+impl SidKind for Content {
+    const PREFIXES: &'static [&'static str] = &["HX"];
+}
+
+impl SidKind for Media {
+    const PREFIXES: &'static [&'static str] = &["ME"];
+}
+
+impl SidKind for Message {
+    const PREFIXES: &'static [&'static str] = &["SM", "MM"];
+}
+
+impl SidKind for MessagingService {
+    const PREFIXES: &'static [&'static str] = &["MG"];
+}
+
+/// The `Sid` struct is a validated Twilio SID of the kind `K`.
+///
+/// # Creating
+///
+/// ```rust
+/// use fullsend::sid::{self, Sid};
+///
+/// let sid = Sid::<sid::Message>::parse("SM0123456789abcdef0123456789abcdef")?;
+/// assert_eq!("SM0123456789abcdef0123456789abcdef", sid.as_str());
+/// # Ok::<(), fullsend::sid::SidParseError>(())
+/// ```
+pub struct Sid<K> {
+    kind: PhantomData<fn() -> K>,
+    sid: String,
+}
+
+/// The `SidParseError` enum represents the various types of errors that can
+/// arise when attempting to parse a `Sid`.
+#[derive(Debug, thiserror::Error, PartialEq)]
+#[non_exhaustive]
+pub enum SidParseError {
+    /// This error occurs when the SID doesn't start with a prefix that's valid
+    /// for the expected kind. The prefix that was found is contained in this
+    /// error.
+    #[error("SID has unexpected prefix {0:?}")]
+    WrongKind(String),
+    /// This error occurs when the SID isn't a two-letter prefix followed by 32
+    /// alphanumeric characters.
+    #[error("SID is malformed")]
+    Malformed,
+}
+
+impl<K: SidKind> Sid<K> {
+    /// This function validates `sid` and returns it as a `Sid`.
+    pub fn parse(sid: &str) -> Result<Self, SidParseError> {
+        Self::validate(sid)?;
+        Ok(Self {
+            kind: PhantomData,
+            sid: sid.into(),
+        })
+    }
+
+    fn validate(sid: &str) -> Result<(), SidParseError> {
+        if sid.len() != 34 || !sid.bytes().all(|byte| byte.is_ascii_alphanumeric()) {
+            return Err(SidParseError::Malformed);
+        }
+        let prefix = &sid[..2];
+        if !K::PREFIXES.contains(&prefix) {
+            return Err(SidParseError::WrongKind(prefix.into()));
+        }
+        Ok(())
+    }
+}
+
+impl<K> Sid<K> {
+    /// This function returns the SID as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.sid
+    }
+}
+
+impl<K> Clone for Sid<K> {
+    fn clone(&self) -> Self {
+        Self {
+            kind: PhantomData,
+            sid: self.sid.clone(),
+        }
+    }
+}
+
+impl<K> fmt::Debug for Sid<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Sid").field(&self.sid).finish()
+    }
+}
+
+impl<K> fmt::Display for Sid<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.sid)
+    }
+}
+
+impl<K> PartialEq for Sid<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.sid == other.sid
+    }
+}
+
+impl<K> Eq for Sid<K> {}
+
+impl<K> Hash for Sid<K> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.sid.hash(state);
+    }
+}
+
+impl<'de, K: SidKind> Deserialize<'de> for Sid<K> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let sid = String::deserialize(deserializer)?;
+        Self::validate(&sid).map_err(de::Error::custom)?;
+        Ok(Self {
+            kind: PhantomData,
+            sid,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_accepts_valid_sid() {
+        let sid = Sid::<Message>::parse("MM0123456789abcdef0123456789abcdef");
+        assert!(sid.is_ok());
+    }
+
+    #[test]
+    fn parse_rejects_malformed_sid() {
+        let sid = Sid::<Message>::parse("SM0123");
+        assert_eq!(Err(SidParseError::Malformed), sid);
+    }
+
+    #[test]
+    fn parse_rejects_wrong_kind() {
+        let sid = Sid::<MessagingService>::parse("SM0123456789abcdef0123456789abcdef");
+        assert_eq!(Err(SidParseError::WrongKind("SM".into())), sid);
+    }
+
+    #[test]
+    fn deserialize_validates_kind() {
+        let json = r#""AC0123456789abcdef0123456789abcdef""#;
+        assert!(serde_json::from_str::<Sid<Account>>(json).is_ok());
+        assert!(serde_json::from_str::<Sid<ApiKey>>(json).is_err());
+    }
+}
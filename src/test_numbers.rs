@@ -0,0 +1,33 @@
+//! This module provides the magic phone numbers that Twilio's test credentials
+//! accept. A `Client` created with `Client::test_credentials` doesn't send real
+//! messages; instead, Twilio responds based on which of these numbers are used.
+//!
+//! Any other `from` number is rejected, while any other valid `to` number is
+//! accepted.
+
+/// A `from` number that is valid, so the message is accepted.
+pub const FROM_VALID: &str = "+15005550006";
+
+/// A `from` number that is invalid.
+pub const FROM_INVALID: &str = "+15005550001";
+
+/// A `from` number that isn't owned by the account or can't send SMS.
+pub const FROM_NOT_OWNED: &str = "+15005550007";
+
+/// A `from` number whose SMS queue is full.
+pub const FROM_QUEUE_FULL: &str = "+15005550008";
+
+/// A `to` number that is invalid.
+pub const TO_INVALID: &str = "+15005550001";
+
+/// A `to` number that can't be routed to.
+pub const TO_UNROUTABLE: &str = "+15005550002";
+
+/// A `to` number the account doesn't have international permissions for.
+pub const TO_NO_INTERNATIONAL_PERMISSIONS: &str = "+15005550003";
+
+/// A `to` number that has blocked the account.
+pub const TO_BLOCKED: &str = "+15005550004";
+
+/// A `to` number that can't receive SMS.
+pub const TO_SMS_INCAPABLE: &str = "+15005550009";
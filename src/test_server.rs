@@ -0,0 +1,174 @@
+//! This module provides a local HTTP server that stands in for Twilio in
+//! tests, so that the `Client` can be tested without reaching Twilio.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+/// The `Request` struct represents a request the `TestServer` received.
+#[derive(Clone, Debug)]
+pub(crate) struct Request {
+    pub(crate) body: String,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) method: String,
+    pub(crate) path: String,
+}
+
+impl Request {
+    /// This function returns the value of the header `name`, if the request
+    /// had it.
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// This function parses the body of the request as a form.
+    pub(crate) fn form(&self) -> HashMap<String, String> {
+        form_urlencoded::parse(self.body.as_bytes())
+            .into_owned()
+            .collect()
+    }
+}
+
+/// The `Response` struct represents a response for the `TestServer` to send.
+#[derive(Clone, Debug)]
+pub(crate) struct Response {
+    body: String,
+    headers: Vec<(String, String)>,
+    status: u16,
+}
+
+impl Response {
+    /// This function creates a `Response` with the given status and body.
+    pub(crate) fn new(status: u16, body: impl Into<String>) -> Self {
+        Self {
+            body: body.into(),
+            headers: Vec::new(),
+            status,
+        }
+    }
+
+    /// This function adds a header to the response.
+    pub(crate) fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+}
+
+/// The `TestServer` struct is a local HTTP server that answers the requests it
+/// receives with the responses it was started with, in order, and keeps the
+/// requests so that they can be checked.
+pub(crate) struct TestServer {
+    requests: Arc<Mutex<Vec<Request>>>,
+    url: String,
+}
+
+impl TestServer {
+    /// This function starts a `TestServer` that answers with `responses`. Once
+    /// they've all been sent, the server stops accepting connections. It must
+    /// be called from within a runtime, which the server runs on.
+    pub(crate) async fn start(responses: Vec<Response>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let server_requests = Arc::clone(&requests);
+        tokio::spawn(async move {
+            for response in responses {
+                let mut stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    Err(_) => return,
+                };
+                // each connection only gets one request, so that the client
+                // can't send the next one before we're ready for it
+                let request = read_request(&mut stream).await;
+                server_requests.lock().unwrap().push(request);
+                write_response(&mut stream, response).await;
+            }
+        });
+        Self { requests, url }
+    }
+
+    /// This function returns the URL of a port nothing is listening on, for
+    /// testing what happens when Twilio can't be reached.
+    pub(crate) async fn unreachable_url() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        format!("http://{}/2010-04-01", listener.local_addr().unwrap())
+    }
+
+    /// This function returns the requests the server has received so far.
+    pub(crate) fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// This function returns the URL of the server, to use as a `Client`'s
+    /// base URL.
+    pub(crate) fn url(&self) -> String {
+        format!("{}/2010-04-01", self.url)
+    }
+}
+
+/// This function reads an HTTP/1.1 request from `stream`.
+async fn read_request(stream: &mut TcpStream) -> Request {
+    let mut buffer = Vec::new();
+    let mut chunk = [0; 4096];
+    // read until we have all of the headers
+    let header_end = loop {
+        if let Some(header_end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break header_end;
+        }
+        let read = stream.read(&mut chunk).await.unwrap();
+        assert_ne!(0, read, "connection closed before the request was sent");
+        buffer.extend_from_slice(&chunk[..read]);
+    };
+    let head = String::from_utf8_lossy(&buffer[..header_end]).into_owned();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next().unwrap().split(' ');
+    let method = request_line.next().unwrap().to_string();
+    let path = request_line.next().unwrap().to_string();
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.to_string(), value.trim().to_string()))
+        .collect();
+    // then read the rest of the body, if there is one
+    let content_length = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("Content-Length"))
+        .map_or(0, |(_, value)| value.parse().unwrap());
+    let mut body = buffer[header_end + 4..].to_vec();
+    while body.len() < content_length {
+        let read = stream.read(&mut chunk).await.unwrap();
+        assert_ne!(0, read, "connection closed before the body was sent");
+        body.extend_from_slice(&chunk[..read]);
+    }
+    Request {
+        body: String::from_utf8_lossy(&body).into_owned(),
+        headers,
+        method,
+        path,
+    }
+}
+
+/// This function writes `response` to `stream` and closes the connection. The
+/// client may have given up on the request already, so errors are ignored.
+async fn write_response(stream: &mut TcpStream, response: Response) {
+    let mut raw = format!(
+        "HTTP/1.1 {} Test\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        response.body.len()
+    );
+    for (name, value) in &response.headers {
+        raw.push_str(&format!("{name}: {value}\r\n"));
+    }
+    raw.push_str("\r\n");
+    raw.push_str(&response.body);
+    let _ = stream.write_all(raw.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}